@@ -0,0 +1,107 @@
+use std::{collections::BTreeSet, path::PathBuf, process::ExitCode};
+
+use cargo_metadata::{MetadataCommand, Package};
+use clap::{Args, Parser};
+use maudfmt::{module_files, process, Config, Mode, Source, ERROR};
+use miette::{miette, Report};
+
+#[derive(Parser)]
+#[command(name = "cargo", bin_name = "cargo")]
+enum Cargo {
+    /// Format Maud markup in every target of the workspace
+    #[command(version, about)]
+    Maudfmt(MaudfmtArgs),
+}
+
+#[derive(Args)]
+struct MaudfmtArgs {
+    /// Package to format, can be given multiple times
+    #[arg(short, long = "package", value_name = "SPEC")]
+    packages: Vec<String>,
+
+    /// Format all packages in the workspace
+    #[arg(long, conflicts_with = "packages")]
+    all: bool,
+
+    /// Print a diff instead of rewriting files and exit with status 1 if anything would change
+    #[arg(long)]
+    check: bool,
+
+    /// Path to Cargo.toml
+    #[arg(long, value_name = "PATH")]
+    manifest_path: Option<PathBuf>,
+
+    /// Additional macro paths to format as Maud markup, e.g. `hypertext::maud`
+    #[arg(long = "macro", value_name = "PATH")]
+    macros: Vec<String>,
+
+    /// Path to the config file, defaults to the nearest `maudfmt.toml`
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+}
+
+fn target_files(package: &Package) -> Vec<PathBuf> {
+    package
+        .targets
+        .iter()
+        .flat_map(|target| module_files(target.src_path.as_std_path()))
+        .collect()
+}
+
+fn run(args: MaudfmtArgs) -> Result<ExitCode, Report> {
+    let mut config = Config::load(args.config.as_deref())?;
+    config.macros.extend(args.macros);
+
+    let mut command = MetadataCommand::new();
+    command
+        .no_deps()
+        .other_options(vec![String::from("--offline")]);
+
+    if let Some(manifest_path) = &args.manifest_path {
+        command.manifest_path(manifest_path);
+    }
+
+    let metadata = command
+        .exec()
+        .map_err(|e| miette!("Error reading cargo metadata: {}", e))?;
+
+    let packages = if !args.packages.is_empty() {
+        args.packages
+            .iter()
+            .map(|name| {
+                metadata
+                    .workspace_packages()
+                    .into_iter()
+                    .find(|package| package.name.as_str() == name)
+                    .ok_or_else(|| miette!("Package `{}` not found in workspace", name))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    } else if args.all || !metadata.workspace_default_members.is_available() {
+        metadata.workspace_packages()
+    } else {
+        metadata.workspace_default_packages()
+    };
+
+    let files: BTreeSet<PathBuf> = packages.into_iter().flat_map(target_files).collect();
+
+    let sources = files
+        .into_iter()
+        .map(Source::file)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mode = if args.check { Mode::Check } else { Mode::Write };
+
+    process(&sources, &config, mode)
+}
+
+fn main() -> ExitCode {
+    let Cargo::Maudfmt(args) = Cargo::parse();
+
+    match run(args) {
+        Ok(code) => code,
+        Err(report) => {
+            eprintln!("{:?}", report);
+            ExitCode::from(ERROR)
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use miette::{miette, Report};
+use serde::Deserialize;
+
+const CONFIG_FILE_NAMES: [&str; 2] = ["maudfmt.toml", ".maudfmt.toml"];
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub macros: Vec<String>,
+    pub max_width: usize,
+    pub blank_lines_upper_bound: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            macros: Vec::new(),
+            max_width: 100,
+            blank_lines_upper_bound: 1,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_file(path: &Path) -> Result<Config, Report> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| miette!("Error reading config {}: {}", path.display(), e))?;
+
+        toml::from_str(&content)
+            .map_err(|e| miette!("Error parsing config {}: {}", path.display(), e))
+    }
+
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .flat_map(|dir| CONFIG_FILE_NAMES.iter().map(move |name| dir.join(name)))
+            .find(|path| path.is_file())
+    }
+
+    pub fn load(path: Option<&Path>) -> Result<Config, Report> {
+        let path = match path {
+            Some(path) => Some(path.to_path_buf()),
+            None => env::current_dir().ok().and_then(|dir| Config::find(&dir)),
+        };
+
+        match path {
+            Some(path) => Config::from_file(&path),
+            None => Ok(Config::default()),
+        }
+    }
+}
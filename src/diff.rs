@@ -0,0 +1,9 @@
+use similar::TextDiff;
+
+pub fn unified_diff(name: &str, original: &str, formatted: &str) -> String {
+    TextDiff::from_lines(original, formatted)
+        .unified_diff()
+        .context_radius(3)
+        .header(name, name)
+        .to_string()
+}
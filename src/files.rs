@@ -0,0 +1,141 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use ignore::WalkBuilder;
+use miette::{miette, Report};
+use syn::{Expr, ExprLit, Item, Lit, Meta};
+use tempfile::NamedTempFile;
+
+fn is_rust_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "rs")
+}
+
+pub fn collect_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>, Report> {
+    let mut files = Vec::new();
+
+    for path in paths {
+        if path.is_file() {
+            files.push(path.clone());
+            continue;
+        }
+
+        if !path.is_dir() {
+            return Err(miette!("No such file or directory: {}", path.display()));
+        }
+
+        for entry in WalkBuilder::new(path)
+            .require_git(false)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .build()
+        {
+            let entry = entry.map_err(|e| miette!("Error walking {}: {}", path.display(), e))?;
+
+            if entry.file_type().is_some_and(|t| t.is_file()) && is_rust_file(entry.path()) {
+                files.push(entry.into_path());
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+fn path_attr(item: &syn::ItemMod) -> Option<String> {
+    item.attrs.iter().find_map(|attr| match &attr.meta {
+        Meta::NameValue(meta) if meta.path.is_ident("path") => match &meta.value {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(path),
+                ..
+            }) => Some(path.value()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+fn visit_module_items(
+    items: &[Item],
+    file_dir: &Path,
+    dir: &Path,
+    inline: bool,
+    files: &mut Vec<PathBuf>,
+) {
+    for item in items {
+        let Item::Mod(item) = item else {
+            continue;
+        };
+
+        let name = item.ident.to_string();
+        let name = name.trim_start_matches("r#");
+        let path = path_attr(item);
+
+        match (&item.content, path) {
+            (Some((_, items)), path) => {
+                let dir = dir.join(path.as_deref().unwrap_or(name));
+                visit_module_items(items, file_dir, &dir, true, files);
+            }
+            // `#[path]` is relative to the current file, unless nested in an inline module
+            (None, Some(path)) => {
+                let path = if inline { dir } else { file_dir }.join(path);
+                let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+                visit_module_file(&path, &dir, files);
+            }
+            (None, None) => {
+                let file = dir.join(format!("{}.rs", name));
+
+                if file.is_file() {
+                    visit_module_file(&file, &dir.join(name), files);
+                } else {
+                    visit_module_file(&dir.join(name).join("mod.rs"), &dir.join(name), files);
+                }
+            }
+        }
+    }
+}
+
+fn visit_module_file(path: &Path, dir: &Path, files: &mut Vec<PathBuf>) {
+    if !path.is_file() || files.iter().any(|file| file == path) {
+        return;
+    }
+
+    files.push(path.to_path_buf());
+
+    // Unparseable files are still formatted, which reports the error
+    let Some(file) = fs::read_to_string(path)
+        .ok()
+        .and_then(|code| syn::parse_file(&code).ok())
+    else {
+        return;
+    };
+
+    let file_dir = path.parent().unwrap_or(Path::new(""));
+    visit_module_items(&file.items, file_dir, dir, false, files);
+}
+
+/// Files reachable from a crate root through `mod` declarations
+pub fn module_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let dir = root.parent().unwrap_or(Path::new(""));
+
+    visit_module_file(root, dir, &mut files);
+
+    files
+}
+
+pub fn write_atomic(path: &Path, content: &str) -> Result<(), Report> {
+    let error = |e: std::io::Error| miette!("Error writing {}: {}", path.display(), e);
+
+    // Renaming over a symlink would replace the link instead of the file it points to
+    let target = fs::canonicalize(path).map_err(error)?;
+    let permissions = fs::metadata(&target).map_err(error)?.permissions();
+
+    let mut file =
+        NamedTempFile::new_in(target.parent().unwrap_or(Path::new("."))).map_err(error)?;
+    file.write_all(content.as_bytes()).map_err(error)?;
+    file.as_file().set_permissions(permissions).map_err(error)?;
+    file.persist(&target).map_err(|e| error(e.error))?;
+
+    Ok(())
+}
@@ -0,0 +1,273 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+const INDENT_WIDTH: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GroupId(usize);
+
+pub fn group_id() -> GroupId {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    GroupId(NEXT.fetch_add(1, Ordering::Relaxed))
+}
+
+#[derive(Clone, Debug)]
+pub enum Doc {
+    Nil,
+    /// Printed verbatim, may span multiple lines
+    Text(String),
+    /// A space, or a newline if the enclosing group breaks
+    Line,
+    /// Nothing, or a newline if the enclosing group breaks
+    SoftLine,
+    /// Always a newline, forces every enclosing group to break
+    HardLine,
+    Concat(Vec<Doc>),
+    Indent(Box<Doc>),
+    Group(Box<Doc>, Option<GroupId>),
+    /// A group that also breaks whenever the group with the given id broke
+    LinkedGroup(GroupId, Box<Doc>),
+    /// Prints the first doc whose lines all fit, or the last one, the id breaks if it spans lines
+    Choice(Vec<Doc>, Option<GroupId>),
+}
+
+pub fn nil() -> Doc {
+    Doc::Nil
+}
+
+pub fn text(s: impl Into<String>) -> Doc {
+    Doc::Text(s.into())
+}
+
+pub fn line() -> Doc {
+    Doc::Line
+}
+
+pub fn softline() -> Doc {
+    Doc::SoftLine
+}
+
+pub fn hardline() -> Doc {
+    Doc::HardLine
+}
+
+pub fn concat(docs: Vec<Doc>) -> Doc {
+    Doc::Concat(docs)
+}
+
+pub fn indent(doc: Doc) -> Doc {
+    Doc::Indent(Box::new(doc))
+}
+
+pub fn group(doc: Doc) -> Doc {
+    Doc::Group(Box::new(doc), None)
+}
+
+pub fn group_with_id(id: GroupId, doc: Doc) -> Doc {
+    Doc::Group(Box::new(doc), Some(id))
+}
+
+pub fn linked_group(id: GroupId, doc: Doc) -> Doc {
+    Doc::LinkedGroup(id, Box::new(doc))
+}
+
+pub fn choice(docs: Vec<Doc>) -> Doc {
+    Doc::Choice(docs, None)
+}
+
+pub fn choice_with_id(id: GroupId, docs: Vec<Doc>) -> Doc {
+    Doc::Choice(docs, Some(id))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+type Command<'a> = (usize, Mode, &'a Doc);
+
+fn width(s: &str) -> usize {
+    s.chars().count()
+}
+
+fn fits(
+    next: Command,
+    rest: &[Command],
+    modes: &HashMap<GroupId, Mode>,
+    mut remaining: isize,
+) -> bool {
+    let mut commands = vec![next];
+    let mut rest = rest.iter().rev();
+
+    while remaining >= 0 {
+        let (indent, mode, doc) = match commands.pop() {
+            Some(command) => command,
+            None => match rest.next() {
+                Some(command) => *command,
+                None => return true,
+            },
+        };
+
+        match doc {
+            Doc::Nil => {}
+            Doc::Text(s) => match s.split_once('\n') {
+                Some(_) if mode == Mode::Flat => return false,
+                Some((first, _)) => return remaining >= width(first) as isize,
+                None => remaining -= width(s) as isize,
+            },
+            Doc::Line | Doc::SoftLine if mode == Mode::Break => return true,
+            Doc::Line => remaining -= 1,
+            Doc::SoftLine => {}
+            Doc::HardLine => return mode == Mode::Break,
+            Doc::Concat(docs) => {
+                commands.extend(docs.iter().rev().map(|doc| (indent, mode, doc)));
+            }
+            Doc::Indent(doc) => commands.push((indent + INDENT_WIDTH, mode, doc)),
+            Doc::Group(doc, _) => commands.push((indent, mode, doc)),
+            Doc::LinkedGroup(id, doc) => match modes.get(id) {
+                Some(Mode::Break) => commands.push((indent, Mode::Break, doc)),
+                _ => commands.push((indent, mode, doc)),
+            },
+            Doc::Choice(docs, _) => commands.extend(docs.first().map(|doc| (indent, mode, doc))),
+        }
+    }
+
+    false
+}
+
+fn within(out: &str, max_width: usize, column: usize) -> bool {
+    out.split('\n')
+        .enumerate()
+        .all(|(i, line)| if i == 0 { column } else { 0 } + width(line) <= max_width)
+}
+
+fn end_column(out: &str, column: usize) -> usize {
+    match out.rsplit_once('\n') {
+        Some((_, last)) => width(last),
+        None => column + width(out),
+    }
+}
+
+fn newline(out: &mut String, indent: usize) {
+    out.truncate(out.trim_end_matches(' ').len());
+    out.push('\n');
+    out.push_str(&" ".repeat(indent));
+}
+
+fn print_in(doc: &Doc, mode: Mode, max_width: usize, indent: usize, column: usize) -> String {
+    let mut out = String::new();
+    let mut column = column;
+    let mut modes = HashMap::new();
+    let mut commands: Vec<Command> = vec![(indent, mode, doc)];
+
+    while let Some((indent, mode, doc)) = commands.pop() {
+        match doc {
+            Doc::Nil => {}
+            Doc::Text(s) => {
+                out.push_str(s);
+                column = end_column(s, column);
+            }
+            Doc::Line if mode == Mode::Flat => {
+                out.push(' ');
+                column += 1;
+            }
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                newline(&mut out, indent);
+                column = indent;
+            }
+            Doc::Concat(docs) => {
+                commands.extend(docs.iter().rev().map(|doc| (indent, mode, doc)));
+            }
+            Doc::Indent(doc) => commands.push((indent + INDENT_WIDTH, mode, doc)),
+            Doc::Group(doc, id) => {
+                let remaining = max_width as isize - column as isize;
+
+                let mode = if mode == Mode::Flat
+                    || fits((indent, Mode::Flat, doc), &commands, &modes, remaining)
+                {
+                    Mode::Flat
+                } else {
+                    Mode::Break
+                };
+
+                if let Some(id) = id {
+                    modes.insert(*id, mode);
+                }
+
+                commands.push((indent, mode, doc));
+            }
+            Doc::LinkedGroup(id, doc) => {
+                let remaining = max_width as isize - column as isize;
+
+                let mode = if modes.get(id) == Some(&Mode::Break) {
+                    Mode::Break
+                } else if mode == Mode::Flat
+                    || fits((indent, Mode::Flat, doc), &commands, &modes, remaining)
+                {
+                    Mode::Flat
+                } else {
+                    Mode::Break
+                };
+
+                commands.push((indent, mode, doc));
+            }
+            Doc::Choice(docs, id) => {
+                // Inside a flat group the first doc is the one that was measured to fit
+                let candidates = if mode == Mode::Flat {
+                    &docs[..docs.len().min(1)]
+                } else {
+                    &docs[..]
+                };
+
+                // The chosen doc is printed once and its output reused, nested choices would
+                // otherwise be printed again at every level
+                let mut printed = None;
+
+                for doc in candidates {
+                    let out = print_in(doc, mode, max_width, indent, column);
+
+                    // Whatever follows on the last line has to fit as well
+                    let remaining = max_width as isize - end_column(&out, column) as isize;
+                    let done = within(&out, max_width, column)
+                        && fits(
+                            (indent, Mode::Flat, &Doc::Nil),
+                            &commands,
+                            &modes,
+                            remaining,
+                        );
+
+                    printed = Some(out);
+
+                    if done {
+                        break;
+                    }
+                }
+
+                if let Some(printed) = printed {
+                    if let Some(id) = id {
+                        let mode = if printed.contains('\n') {
+                            Mode::Break
+                        } else {
+                            Mode::Flat
+                        };
+                        modes.insert(*id, mode);
+                    }
+
+                    out.push_str(&printed);
+                    column = end_column(&printed, column);
+                }
+            }
+        }
+    }
+
+    out
+}
+
+pub fn print(doc: &Doc, max_width: usize, indent: usize, column: usize) -> String {
+    print_in(doc, Mode::Break, max_width, indent, column)
+}
@@ -0,0 +1,722 @@
+mod doc;
+mod rust;
+
+use crate::{
+    parser::{
+        ends_with_line_comment, has_multiline_literal, is_html_name, nested_markup, AttrItem,
+        Attribute, AttributeValue, Block, Child, Comment, Condition, ControlStructure, Element,
+        ElementBody, Else, For, If, Let, LetCondition, Markup, Match, MatchArm, Name, Node,
+        Optional, Shorthand, Splice, Toggle, While,
+    },
+    Config,
+};
+
+use self::{
+    doc::{
+        choice, choice_with_id, concat, group, group_id, group_with_id, hardline, indent, line,
+        linked_group, softline, text, Doc, GroupId,
+    },
+    rust::{format_rust_expr, format_rust_stmt},
+};
+
+fn reindent(expr: &str) -> Doc {
+    let mut lines = expr.lines();
+    let first = lines.next().unwrap_or_default().trim();
+    let rest: Vec<&str> = lines.collect();
+
+    let min_indent = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or_default();
+
+    let mut docs = Vec::new();
+
+    for line in rest {
+        docs.push(hardline());
+
+        if !line.trim().is_empty() {
+            docs.push(text(line[min_indent..].trim_end()));
+        }
+    }
+
+    concat(vec![text(first), indent(concat(docs))])
+}
+
+fn lines(s: &str) -> Doc {
+    let mut docs = Vec::new();
+
+    for (i, line) in s.lines().enumerate() {
+        if i > 0 {
+            docs.push(hardline());
+        }
+
+        docs.push(text(line));
+    }
+
+    concat(docs)
+}
+
+// Continuation lines are re-indented to the markup depth, keeping their relative indentation
+fn format_verbatim(s: &str, min_indent: Option<usize>) -> Doc {
+    let Some(min_indent) = min_indent else {
+        return text(s);
+    };
+
+    let lines: Vec<&str> = s.split('\n').collect();
+    let mut docs = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        // The last line carries on with whatever follows the segment
+        let line = if i + 1 < lines.len() {
+            line.trim_end()
+        } else {
+            line
+        };
+
+        if i == 0 {
+            docs.push(text(line));
+        } else {
+            let line = line.get(min_indent..).unwrap_or(line.trim_start());
+            docs.push(indent(concat(vec![hardline(), text(line)])));
+        }
+    }
+
+    concat(docs)
+}
+
+fn format_expr(expr: &str, config: &Config) -> Doc {
+    let markup = nested_markup(expr);
+
+    let mut segments = Vec::new();
+    let mut last = 0;
+
+    for (range, _) in &markup {
+        segments.push(&expr[last..range.start]);
+        last = range.end;
+    }
+
+    segments.push(&expr[last..]);
+
+    // Re-indenting would change the contents of multi-line string literals
+    let min_indent = if has_multiline_literal(expr) {
+        None
+    } else {
+        segments
+            .iter()
+            .flat_map(|segment| segment.split('\n').skip(1))
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+    };
+
+    let mut docs = Vec::new();
+    let mut continued = false;
+
+    for (segment, (_, markup)) in segments.iter().zip(&markup) {
+        docs.push(format_verbatim(segment, min_indent));
+        continued |= min_indent.is_some() && segment.contains('\n');
+
+        // Markup on a continuation line moves along with it
+        let nodes = format_nodes(&markup.nodes, hardline(), config);
+        docs.push(if continued { indent(nodes) } else { nodes });
+    }
+
+    docs.push(format_verbatim(&expr[last..], min_indent));
+
+    concat(docs)
+}
+
+fn format_comments(comments: &[Comment]) -> Doc {
+    concat(
+        comments
+            .iter()
+            .flat_map(|comment| [format_comment(comment), hardline()])
+            .collect(),
+    )
+}
+
+fn format_inline_comments(comments: &[Comment]) -> Doc {
+    concat(
+        comments
+            .iter()
+            .flat_map(|comment| match comment {
+                Comment::Line(_) => [format_comment(comment), hardline()],
+                Comment::Block(_) => [format_comment(comment), text(" ")],
+            })
+            .collect(),
+    )
+}
+
+// The layouts to choose from at the header's column, with `fallback` for when none fit
+fn format_header(header: &str, fallback: Doc) -> Vec<Doc> {
+    let layouts = format_rust_stmt(&format!("{} {{}}", header)).unwrap_or_default();
+
+    let mut docs: Vec<Doc> = layouts
+        .iter()
+        .filter_map(|layout| {
+            let header = layout.strip_suffix("{}")?;
+
+            // Headers that had to be broken up open their body on a line of its own
+            let separator = if header.ends_with('\n') {
+                hardline()
+            } else {
+                text(" ")
+            };
+
+            Some(concat(vec![lines(header.trim_end()), separator]))
+        })
+        .collect();
+
+    docs.push(fallback);
+    docs
+}
+
+// A trailing line comment would swallow whatever follows it on the same line
+fn trailing_separator(expr: &str) -> Doc {
+    if ends_with_line_comment(expr) {
+        hardline()
+    } else {
+        text(" ")
+    }
+}
+
+// The body always breaks once its header spans several lines
+fn format_body(block: &Block, header: GroupId, config: &Config) -> Doc {
+    linked_group(header, format_block_contents(block, config))
+}
+
+fn format_match_arm_heads(match_arm: &MatchArm) -> Vec<Doc> {
+    let guard = match_arm
+        .guard
+        .map(|guard| format!(" if {}", guard))
+        .unwrap_or_default();
+
+    let layouts = format_rust_stmt(&format!(
+        "match x {{ {}{} => {{}} }}",
+        match_arm.pattern, guard
+    ))
+    .unwrap_or_default();
+
+    layouts
+        .iter()
+        .filter_map(|layout| {
+            let arm = match layout.strip_prefix("match x { ") {
+                Some(flat) => flat.strip_suffix(" }")?,
+                None => layout.strip_prefix("match x {\n")?.strip_suffix("\n}")?,
+            };
+
+            let head = arm
+                .strip_suffix(" => {}")?
+                .lines()
+                .map(|line| line.strip_prefix("    ").unwrap_or(line))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            Some(concat(vec![lines(&head), text(" ")]))
+        })
+        .collect()
+}
+
+fn format_match_arm(match_arm: &MatchArm, config: &Config) -> Doc {
+    let mut fallback = vec![text(match_arm.pattern)];
+
+    if let Some(guard) = match_arm.guard {
+        fallback.push(text(format!(" if {}", guard)));
+    }
+
+    fallback.push(trailing_separator(
+        match_arm.guard.unwrap_or(match_arm.pattern),
+    ));
+
+    let mut heads = format_match_arm_heads(match_arm);
+    heads.push(concat(fallback));
+
+    let mut docs = vec![
+        format_comments(&match_arm.comments),
+        choice(heads),
+        text("=> "),
+    ];
+
+    let body = concat(vec![
+        format_inline_comments(&match_arm.body_comments),
+        format_node(&match_arm.body, config),
+    ]);
+
+    // A line comment pushes the body onto the next line, indented under the pattern
+    if match_arm
+        .body_comments
+        .iter()
+        .any(|comment| matches!(comment, Comment::Line(_)))
+    {
+        docs.push(indent(body));
+    } else {
+        docs.push(body);
+    }
+
+    if !matches!(match_arm.body, Node::Block(_)) {
+        docs.push(text(","));
+    }
+
+    concat(docs)
+}
+
+fn format_match(r#match: &Match, config: &Config) -> Doc {
+    let fallback = concat(vec![
+        text("match "),
+        format_expr(r#match.scrut, config),
+        trailing_separator(r#match.scrut),
+    ]);
+
+    let header = format_header(&format!("match {}", r#match.scrut), fallback);
+
+    let mut docs = vec![choice(header), text("{")];
+
+    if !r#match.arms.is_empty() || !r#match.trailing_comments.is_empty() {
+        let mut arms = Vec::new();
+
+        for match_arm in &r#match.arms {
+            arms.push(hardline());
+            arms.push(format_match_arm(match_arm, config));
+        }
+
+        for comment in &r#match.trailing_comments {
+            arms.push(hardline());
+            arms.push(format_comment(comment));
+        }
+
+        docs.push(indent(concat(arms)));
+        docs.push(hardline());
+    }
+
+    docs.push(text("}"));
+
+    concat(docs)
+}
+
+fn format_let(r#let: &Let, config: &Config) -> Doc {
+    let separator = if ends_with_line_comment(r#let.expr) {
+        hardline()
+    } else {
+        doc::nil()
+    };
+
+    let fallback = concat(vec![
+        text("let "),
+        format_expr(r#let.expr, config),
+        separator,
+        text(";"),
+    ]);
+
+    let layouts = format_rust_stmt(&format!("let {};", r#let.expr)).unwrap_or_default();
+    let mut docs: Vec<Doc> = layouts.iter().map(|layout| lines(layout)).collect();
+    docs.push(fallback);
+
+    choice(docs)
+}
+
+fn format_for(r#for: &For, config: &Config) -> Doc {
+    let fallback = concat(vec![
+        text(format!("for {} in ", r#for.pattern)),
+        format_expr(r#for.expr, config),
+        trailing_separator(r#for.expr),
+    ]);
+
+    let header = format_header(
+        &format!("for {} in {}", r#for.pattern, r#for.expr),
+        fallback,
+    );
+    let id = group_id();
+
+    concat(vec![
+        choice_with_id(id, header),
+        format_body(&r#for.body, id, config),
+    ])
+}
+
+fn condition_source(cond: &Condition) -> String {
+    match cond {
+        Condition::Expr(expr) => expr.to_string(),
+        Condition::Let(LetCondition { pattern, expr }) => format!("let {} = {}", pattern, expr),
+    }
+}
+
+fn condition_expr<'a>(cond: &Condition<'a>) -> &'a str {
+    match cond {
+        Condition::Expr(expr) => expr,
+        Condition::Let(LetCondition { expr, .. }) => expr,
+    }
+}
+
+fn format_condition(cond: &Condition, config: &Config) -> Doc {
+    match cond {
+        Condition::Expr(expr) => format_expr(expr, config),
+        Condition::Let(LetCondition { pattern, expr }) => concat(vec![
+            text(format!("let {} = ", pattern)),
+            format_expr(expr, config),
+        ]),
+    }
+}
+
+fn format_while(r#while: &While, config: &Config) -> Doc {
+    let fallback = concat(vec![
+        text("while "),
+        format_condition(&r#while.cond, config),
+        trailing_separator(condition_expr(&r#while.cond)),
+    ]);
+
+    let header = format_header(
+        &format!("while {}", condition_source(&r#while.cond)),
+        fallback,
+    );
+    let id = group_id();
+
+    concat(vec![
+        choice_with_id(id, header),
+        format_body(&r#while.body, id, config),
+    ])
+}
+
+fn format_else(
+    r#else: &Else,
+    comments: &[Comment],
+    body_comments: &[Comment],
+    config: &Config,
+) -> Doc {
+    let separator = if comments.is_empty() {
+        text(" ")
+    } else {
+        concat(vec![hardline(), format_comments(comments)])
+    };
+
+    let body = match r#else {
+        Else::If(r#if) => format_if(r#if, config),
+        Else::Then(block) => format_block_contents(block, config),
+    };
+
+    concat(vec![
+        separator,
+        text("@else "),
+        format_inline_comments(body_comments),
+        body,
+    ])
+}
+
+fn format_if(r#if: &If, config: &Config) -> Doc {
+    // The body never needs forcing here, a broken header already breaks the whole chain
+    let fallback = concat(vec![
+        text("if "),
+        format_condition(&r#if.cond, config),
+        trailing_separator(condition_expr(&r#if.cond)),
+    ]);
+
+    let header = format_header(&format!("if {}", condition_source(&r#if.cond)), fallback);
+
+    let mut docs = vec![choice(header), format_block_contents(&r#if.body, config)];
+
+    if let Some(r#else) = &r#if.else_clause {
+        docs.push(format_else(
+            r#else,
+            &r#if.else_comments,
+            &r#if.else_body_comments,
+            config,
+        ));
+    }
+
+    concat(docs)
+}
+
+fn format_string(string: &str) -> Doc {
+    text(format!("\"{}\"", string))
+}
+
+fn format_comment(comment: &Comment) -> Doc {
+    match comment {
+        Comment::Line(s) => text(format!("//{}", s)),
+        Comment::Block(s) => text(*s),
+    }
+}
+
+fn format_splice(splice: &Splice, config: &Config) -> Doc {
+    // Nested markup is formatted by `format_expr` rather than by prettyplease
+    let layouts = nested_markup(splice.expr)
+        .is_empty()
+        .then(|| format_rust_expr(splice.expr))
+        .flatten();
+
+    let mut docs = vec![text("("), format_expr(splice.expr, config)];
+
+    if ends_with_line_comment(splice.expr) {
+        docs.push(hardline());
+    }
+
+    docs.push(text(")"));
+
+    let Some(layouts) = layouts else {
+        return concat(docs);
+    };
+
+    // The source is kept as is when no layout fits at this column
+    let mut layouts: Vec<Doc> = layouts
+        .iter()
+        .map(|layout| {
+            group(concat(vec![
+                text("("),
+                indent(concat(vec![softline(), lines(layout)])),
+                softline(),
+                text(")"),
+            ]))
+        })
+        .collect();
+
+    layouts.push(concat(docs));
+
+    choice(layouts)
+}
+
+fn can_inline_block(nodes: &[Child], config: &Config) -> bool {
+    let blank_lines = config.blank_lines_upper_bound > 0
+        && nodes.iter().skip(1).any(|child| child.blank_lines > 0);
+
+    !blank_lines
+        && nodes.iter().all(|child| match &child.node {
+            Node::Block(b)
+            | Node::Element(Element {
+                body: ElementBody::Block(b),
+                ..
+            }) => can_inline_block(&b.nodes, config),
+            Node::BlockComment(c) => !c.newline,
+            Node::Comment(_) | Node::ControlStructure(_) => false,
+            _ => true,
+        })
+}
+
+fn format_block(block: &Block, config: &Config) -> Doc {
+    group(format_block_contents(block, config))
+}
+
+fn format_block_contents(block: &Block, config: &Config) -> Doc {
+    if block.nodes.is_empty() {
+        return text("{}");
+    }
+
+    let separator = if can_inline_block(&block.nodes, config) {
+        line()
+    } else {
+        hardline()
+    };
+
+    concat(vec![
+        text("{"),
+        indent(concat(vec![
+            separator.clone(),
+            format_nodes(&block.nodes, separator.clone(), config),
+        ])),
+        separator,
+        text("}"),
+    ])
+}
+
+fn format_optional(optional: &Optional) -> Doc {
+    group(concat(vec![
+        text("["),
+        indent(concat(vec![softline(), reindent(optional.expr)])),
+        softline(),
+        text("]"),
+    ]))
+}
+
+fn format_toggle(toggle: &Toggle) -> Doc {
+    text(format!("[{}]", toggle.cond))
+}
+
+fn format_name(name: &Name) -> Doc {
+    match name {
+        Name::Bare(s) => text(*s),
+        Name::Quoted(s) => format_string(s),
+    }
+}
+
+fn format_attribute_name(name: &Name) -> Doc {
+    match name {
+        Name::Quoted(s) if is_html_name(s) => text(*s),
+        _ => format_name(name),
+    }
+}
+
+fn format_shorthand(shorthand: &Shorthand) -> Doc {
+    match shorthand {
+        Shorthand::Class(name, toggle) => concat(vec![
+            text("."),
+            format_name(name),
+            toggle.as_ref().map_or(doc::nil(), format_toggle),
+        ]),
+        Shorthand::Id(name) => concat(vec![text("#"), format_name(name)]),
+    }
+}
+
+fn format_attribute(attr: &Attribute, config: &Config) -> Doc {
+    let value = match &attr.value {
+        AttributeValue::String(s) => concat(vec![text("="), format_string(s)]),
+        AttributeValue::Splice(s) => concat(vec![text("="), format_splice(s, config)]),
+        AttributeValue::Block(b) => concat(vec![text("="), format_block(b, config)]),
+        AttributeValue::Toggle(t) => format_toggle(t),
+        AttributeValue::Optional(o) => concat(vec![text("="), format_optional(o)]),
+        AttributeValue::Empty => doc::nil(),
+    };
+
+    concat(vec![format_attribute_name(&attr.name), value])
+}
+
+fn format_attr_item(item: &AttrItem, config: &Config) -> Doc {
+    match item {
+        AttrItem::Shorthand(s) => format_shorthand(s),
+        AttrItem::Attribute(a) => format_attribute(a, config),
+        AttrItem::Comment(c) => format_comment(c),
+    }
+}
+
+fn is_line_comment(item: &AttrItem) -> bool {
+    matches!(item, AttrItem::Comment(Comment::Line(_)))
+}
+
+fn format_element(element: &Element, config: &Config) -> Doc {
+    let mut docs = Vec::new();
+
+    if let Some(name) = &element.name {
+        docs.push(format_name(name));
+    }
+
+    // The first shorthand stays attached to the name, the ones right after it only move to
+    // continuation lines when the tag is too long, everything else keeps its source order
+    let leading = element
+        .attrs
+        .iter()
+        .take_while(|item| matches!(item, AttrItem::Shorthand(_)))
+        .count();
+
+    let (shorthands, attrs) = element.attrs.split_at(leading);
+    let (first, shorthands) = shorthands.split_at(shorthands.len().min(1));
+
+    for shorthand in first {
+        docs.push(format_attr_item(shorthand, config));
+    }
+
+    // A lone attribute gains nothing from wrapping, its value breaks instead
+    if shorthands.is_empty() && attrs.len() < 2 && !attrs.iter().any(is_line_comment) {
+        for attr in attrs {
+            docs.push(text(" "));
+            docs.push(format_attr_item(attr, config));
+        }
+
+        match &element.body {
+            ElementBody::Void => docs.push(text(";")),
+            ElementBody::Block(b) => {
+                docs.push(text(" "));
+                docs.push(format_block(b, config));
+            }
+        }
+
+        return concat(docs);
+    }
+
+    // A line comment ends its line, so whatever follows it always wraps
+    let trailing_comment = attrs.last().is_some_and(is_line_comment);
+    let mut items = Vec::new();
+
+    // Shorthands break as a group of their own, so wrapping attributes leave them in place
+    items.push(group(concat(
+        shorthands
+            .iter()
+            .flat_map(|shorthand| [softline(), format_attr_item(shorthand, config)])
+            .collect(),
+    )));
+
+    let mut separator = line();
+
+    for attr in attrs {
+        items.push(separator);
+        items.push(format_attr_item(attr, config));
+        separator = if is_line_comment(attr) {
+            hardline()
+        } else {
+            line()
+        };
+    }
+
+    let attrs = indent(concat(items));
+
+    match &element.body {
+        ElementBody::Void => {
+            let separator = if trailing_comment {
+                hardline()
+            } else {
+                doc::nil()
+            };
+
+            docs.push(group(concat(vec![attrs, separator, text(";")])));
+        }
+        ElementBody::Block(b) => {
+            // Once the attributes wrap, the body opens on its own line and always breaks
+            let id = group_id();
+            docs.push(group_with_id(id, concat(vec![attrs, separator])));
+            docs.push(linked_group(id, format_block_contents(b, config)));
+        }
+    }
+
+    concat(docs)
+}
+
+fn format_node(node: &Node, config: &Config) -> Doc {
+    match node {
+        Node::Element(e) => format_element(e, config),
+        Node::Block(b) => format_block(b, config),
+        Node::StrLit(s) => format_string(s),
+        Node::RawStrLit(s) => text(*s),
+        Node::Comment(s) => text(format!("//{}", s)),
+        Node::BlockComment(c) => text(c.text),
+        Node::Splice(s) => format_splice(s, config),
+        Node::ControlStructure(s) => {
+            let doc = match s {
+                ControlStructure::If(i) => group(format_if(i, config)),
+                ControlStructure::For(f) => format_for(f, config),
+                ControlStructure::While(w) => format_while(w, config),
+                ControlStructure::Let(l) => format_let(l, config),
+                ControlStructure::Match(m) => format_match(m, config),
+            };
+
+            concat(vec![text("@"), doc])
+        }
+    }
+}
+
+fn format_nodes(nodes: &[Child], separator: Doc, config: &Config) -> Doc {
+    let mut docs = Vec::new();
+    let mut prev: Option<&Node> = None;
+
+    for child in nodes {
+        if let Some(prev) = prev {
+            let attached = matches!(prev, Node::BlockComment(c) if !c.newline);
+
+            if attached {
+                docs.push(text(" "));
+            } else {
+                let blank_lines = child.blank_lines.min(config.blank_lines_upper_bound);
+                docs.extend(std::iter::repeat_n(hardline(), blank_lines));
+                docs.push(separator.clone());
+            }
+        }
+
+        docs.push(format_node(&child.node, config));
+        prev = Some(&child.node);
+    }
+
+    concat(docs)
+}
+
+pub fn format(markup: Markup, config: &Config, indent: usize, column: usize) -> String {
+    let doc = format_nodes(&markup.nodes, hardline(), config);
+
+    doc::print(&doc, config.max_width, indent, column)
+}
@@ -0,0 +1,147 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{visit::Visit, File, Item, ItemFn, Macro, MacroDelimiter, Stmt};
+
+use crate::parser::has_comments;
+
+#[derive(Default)]
+struct BraceMacroVisitor {
+    found: bool,
+}
+
+impl<'ast> Visit<'ast> for BraceMacroVisitor {
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        self.found |= matches!(mac.delimiter, MacroDelimiter::Brace(_));
+    }
+}
+
+fn has_multiline_literal(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Group(group) => has_multiline_literal(group.stream()),
+        TokenTree::Literal(lit) => lit.to_string().contains('\n'),
+        _ => false,
+    })
+}
+
+// prettyplease wraps at a fixed margin, each level of nesting narrows it by 4 columns down to 60
+const MAX_DEPTH: usize = 8;
+
+fn unparse_at(stmt: &Stmt, depth: usize) -> Option<String> {
+    let mut item: ItemFn = syn::parse_str("fn main() {}").ok()?;
+    item.block.stmts.push(stmt.clone());
+
+    for _ in 1..depth {
+        let mut outer: ItemFn = syn::parse_str("fn main() {}").ok()?;
+        outer.block.stmts.push(Stmt::Item(Item::Fn(item)));
+        item = outer;
+    }
+
+    let file = File {
+        shebang: None,
+        attrs: Vec::new(),
+        items: vec![Item::Fn(item)],
+    };
+
+    let out = prettyplease::unparse(&file);
+    let lines: Vec<&str> = out.lines().collect();
+    let margin = " ".repeat(depth * 4);
+
+    let body = lines
+        .get(depth..lines.len().saturating_sub(depth))?
+        .iter()
+        .map(|line| line.strip_prefix(&margin).unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Some(body)
+}
+
+// Joins a layout onto one line, for widths beyond prettyplease's margin
+fn flatten(layout: &str) -> Option<String> {
+    let lines: Vec<&str> = layout.lines().map(str::trim).collect();
+
+    // Statements inside blocks are never joined
+    if lines.iter().rev().skip(1).any(|line| line.ends_with(';')) {
+        return None;
+    }
+
+    let mut flat = String::new();
+
+    for line in lines {
+        let closing = line.starts_with([')', ']']);
+
+        // Trailing commas only belong to lists that are broken over several lines
+        if line.starts_with([')', ']', '}']) && flat.ends_with(',') {
+            flat.pop();
+        }
+
+        if !(flat.is_empty()
+            || closing
+            || line.starts_with(['.', '?'])
+            || flat.ends_with(['(', '[']))
+        {
+            flat.push(' ');
+        }
+
+        flat.push_str(line);
+    }
+
+    // The joined line has to lay out back into the same code
+    let stmt = syn::parse_str(&flat)
+        .or_else(|_| syn::parse_str(&flat).map(|expr| Stmt::Expr(expr, None)))
+        .ok()?;
+
+    (unparse_at(&stmt, 1)? == layout).then_some(flat)
+}
+
+fn can_unparse(stmt: &Stmt) -> bool {
+    // prettyplease lays out the bodies of `name! { .. }` macros as if they were Rust blocks
+    let mut visitor = BraceMacroVisitor::default();
+    visitor.visit_stmt(stmt);
+
+    // Re-indenting would change the contents of multi-line string literals
+    !visitor.found && !has_multiline_literal(stmt.to_token_stream())
+}
+
+fn unparse(stmt: Stmt) -> Option<Vec<String>> {
+    if !can_unparse(&stmt) {
+        return None;
+    }
+
+    let mut layouts: Vec<String> = Vec::new();
+
+    for depth in 1..=MAX_DEPTH {
+        let layout = unparse_at(&stmt, depth)?;
+
+        if layouts.last() != Some(&layout) {
+            layouts.push(layout);
+        }
+    }
+
+    if let Some(flat) = layouts.first().and_then(|layout| flatten(layout)) {
+        if layouts[0] != flat {
+            layouts.insert(0, flat);
+        }
+    }
+
+    Some(layouts)
+}
+
+/// Layouts of the expression for narrowing widths, widest first
+pub fn format_rust_expr(expr: &str) -> Option<Vec<String>> {
+    // syn drops comments, so code containing them is kept verbatim
+    if has_comments(expr) {
+        return None;
+    }
+
+    unparse(Stmt::Expr(syn::parse_str(expr).ok()?, None))
+}
+
+/// Layouts of the statement for narrowing widths, widest first
+pub fn format_rust_stmt(stmt: &str) -> Option<Vec<String>> {
+    if has_comments(stmt) {
+        return None;
+    }
+
+    unparse(syn::parse_str(stmt).ok()?)
+}
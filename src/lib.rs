@@ -0,0 +1,196 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use miette::{miette, Report};
+use syn::{parse_file, visit::Visit};
+
+mod config;
+mod diff;
+mod error;
+mod files;
+mod formatter;
+mod parser;
+mod resolve;
+mod visitor;
+
+pub use crate::{
+    config::Config,
+    files::{collect_files, module_files},
+};
+use crate::{
+    diff::unified_diff,
+    files::write_atomic,
+    formatter::format,
+    parser::parse_range,
+    resolve::MacroResolver,
+    visitor::{MacroLocation, MacroVisitor},
+};
+
+const TAB_SIZE: usize = 4;
+
+pub const CHECK_FAILED: u8 = 1;
+pub const ERROR: u8 = 2;
+
+fn format_code(
+    input: &str,
+    location: Vec<MacroLocation>,
+    config: &Config,
+) -> Result<String, Report> {
+    let mut out = input.to_string();
+
+    for location in location.iter().rev() {
+        let whitespace: usize = out
+            .lines()
+            .nth(location.start_line - 1)
+            .unwrap()
+            .chars()
+            .take_while(|ch| ch.is_whitespace() && *ch != '\n')
+            .map(|ch| ch.len_utf8())
+            .sum();
+
+        let indent_level = whitespace / TAB_SIZE;
+
+        let line_start = out[..location.byte_range.start]
+            .rfind('\n')
+            .map_or(0, |i| i + 1);
+        let column = out[line_start..location.byte_range.start].chars().count() + 1;
+
+        let markup = match parse_range(input, location.byte_range.clone()) {
+            Ok(markup) => markup,
+            Err(e) if location.strict => return Err(e.into()),
+            Err(e) => {
+                let report = Report::new(e).wrap_err(format!(
+                    "Skipping html! macro on line {} that is not valid Maud markup",
+                    location.start_line
+                ));
+                eprintln!("{:?}", report);
+                continue;
+            }
+        };
+
+        let formatted = format(markup, config, indent_level * TAB_SIZE, column);
+
+        out.replace_range(location.byte_range.clone(), &format!(" {}", &formatted));
+    }
+
+    Ok(out)
+}
+
+pub fn format_source(code: &str, config: &Config) -> Result<String, Report> {
+    let ast = parse_file(code).map_err(|e| miette!("Error parsing Rust code: {}", e))?;
+
+    let mut visitor = MacroVisitor {
+        resolver: MacroResolver::new(&config.macros),
+        locations: Vec::new(),
+    };
+
+    visitor.visit_file(&ast);
+
+    format_code(code, visitor.locations, config)
+}
+
+pub struct Source {
+    pub name: String,
+    pub path: Option<PathBuf>,
+    pub code: String,
+}
+
+impl Source {
+    pub fn stdin() -> Result<Source, Report> {
+        let mut code = String::new();
+
+        io::stdin()
+            .read_to_string(&mut code)
+            .map_err(|e| miette!("Error reading input: {}", e))?;
+
+        Ok(Source {
+            name: String::from("<stdin>"),
+            path: None,
+            code,
+        })
+    }
+
+    pub fn file(path: PathBuf) -> Result<Source, Report> {
+        let code = fs::read_to_string(&path)
+            .map_err(|e| miette!("Error reading {}: {}", path.display(), e))?;
+
+        Ok(Source {
+            name: path.display().to_string(),
+            path: Some(path),
+            code,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// Print the formatted code to stdout
+    Print,
+    /// Print a diff of the changes and report whether anything would change
+    Check,
+    /// Rewrite changed files in place
+    Write,
+}
+
+/// Formats a single source, returning whether it would change
+fn process_source(
+    source: &Source,
+    config: &Config,
+    mode: Mode,
+    multiple: bool,
+) -> Result<bool, Report> {
+    let Source { name, path, code } = source;
+
+    let formatted = format_source(code, config)
+        .map_err(|e| e.wrap_err(format!("Error formatting {}", name)))?;
+
+    match mode {
+        Mode::Check => {
+            if formatted != *code {
+                print!("{}", unified_diff(name, code, &formatted));
+            }
+        }
+        Mode::Write => {
+            if let Some(path) = path.as_deref().filter(|_| formatted != *code) {
+                write_atomic(path, &formatted)?;
+            }
+        }
+        Mode::Print => {
+            if multiple {
+                println!("{}:\n", name);
+            }
+
+            print!("{}", formatted);
+        }
+    }
+
+    Ok(formatted != *code)
+}
+
+pub fn process(sources: &[Source], config: &Config, mode: Mode) -> Result<ExitCode, Report> {
+    let mut changed = false;
+    let mut failed = false;
+
+    // A failing file is reported without stopping the rest, so `--write` never stops halfway
+    for source in sources {
+        match process_source(source, config, mode, sources.len() > 1) {
+            Ok(source_changed) => changed |= source_changed,
+            Err(report) => {
+                eprintln!("{:?}", report);
+                failed = true;
+            }
+        }
+    }
+
+    Ok(if failed {
+        ExitCode::from(ERROR)
+    } else if changed && mode == Mode::Check {
+        ExitCode::from(CHECK_FAILED)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
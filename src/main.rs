@@ -1,87 +1,62 @@
-use std::{
-    io::{self, Read},
-    ops::Range,
-};
+use std::{path::PathBuf, process::ExitCode};
 
-use miette::{miette, Report};
-use syn::{parse_file, spanned::Spanned, visit::Visit, Macro};
+use clap::Parser;
+use maudfmt::{collect_files, process, Config, Mode, Source, ERROR};
+use miette::Report;
 
-mod error;
-mod formatter;
-mod parser;
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Files or directories to format, reads from stdin when omitted
+    paths: Vec<PathBuf>,
 
-use crate::{formatter::format, parser::parse_range};
+    /// Print a diff instead of the formatted code and exit with status 1 if anything would change
+    #[arg(long)]
+    check: bool,
 
-const TAB_SIZE: usize = 4;
+    /// Rewrite files in place instead of printing the formatted code
+    #[arg(short, long, requires = "paths", conflicts_with = "check")]
+    write: bool,
 
-struct MacroLocation {
-    start_line: usize,
-    byte_range: Range<usize>,
-}
-
-struct MacroVisitor {
-    locations: Vec<MacroLocation>,
-}
-
-impl<'ast> Visit<'ast> for MacroVisitor {
-    fn visit_macro(&mut self, macro_item: &'ast Macro) {
-        if let Some(ident) = macro_item.path.segments.span().source_text() {
-            if ident.ends_with("html") {
-                let span = macro_item.span();
-                let start = span.start().line;
-                let range = span.byte_range();
-
-                self.locations.push(MacroLocation {
-                    start_line: start,
-                    byte_range: (range.start + ident.len() + 1)..range.end,
-                });
-            }
-        }
-    }
-}
-
-fn format_code(input: &str, location: Vec<MacroLocation>) -> Result<String, Report> {
-    let mut out = input.to_string();
-
-    for location in location.iter().rev() {
-        let whitespace: usize = out
-            .lines()
-            .nth(location.start_line - 1)
-            .unwrap()
-            .chars()
-            .take_while(|ch| ch.is_whitespace() && *ch != '\n')
-            .map(|ch| ch.len_utf8())
-            .sum();
-
-        let indent_level = whitespace / TAB_SIZE;
+    /// Additional macro paths to format as Maud markup, e.g. `hypertext::maud`
+    #[arg(long = "macro", value_name = "PATH")]
+    macros: Vec<String>,
 
-        let markup = parse_range(input, location.byte_range.clone())?;
-
-        let formatted = format(markup, indent_level);
-
-        out.replace_range(location.byte_range.clone(), &format!(" {}", &formatted));
-    }
-
-    Ok(out)
+    /// Path to the config file, defaults to the nearest `maudfmt.toml`
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
 }
 
-fn main() -> Result<(), Report> {
-    let mut code = String::new();
-
-    io::stdin()
-        .read_to_string(&mut code)
-        .map_err(|e| miette!("Error reading input: {}", e))?;
-
-    let ast = parse_file(&code).unwrap();
-
-    let mut visitor = MacroVisitor {
-        locations: Vec::new(),
+fn run(args: Args) -> Result<ExitCode, Report> {
+    let mut config = Config::load(args.config.as_deref())?;
+    config.macros.extend(args.macros);
+
+    let sources = if args.paths.is_empty() {
+        vec![Source::stdin()?]
+    } else {
+        collect_files(&args.paths)?
+            .into_iter()
+            .map(Source::file)
+            .collect::<Result<_, _>>()?
     };
 
-    visitor.visit_file(&ast);
+    let mode = if args.check {
+        Mode::Check
+    } else if args.write {
+        Mode::Write
+    } else {
+        Mode::Print
+    };
 
-    let code = format_code(&code, visitor.locations)?;
-    print!("{}", code);
+    process(&sources, &config, mode)
+}
 
-    Ok(())
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(code) => code,
+        Err(report) => {
+            eprintln!("{:?}", report);
+            ExitCode::from(ERROR)
+        }
+    }
 }
@@ -0,0 +1,25 @@
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{anychar, not_line_ending},
+    combinator::{not, recognize},
+    multi::many0_count,
+    sequence::{delimited, pair, preceded},
+};
+
+use super::NomResult;
+
+pub fn line_comment(input: &str) -> NomResult<'_, &str> {
+    recognize(pair(tag("//"), not_line_ending))(input)
+}
+
+pub fn block_comment(input: &str) -> NomResult<'_, &str> {
+    recognize(delimited(
+        tag("/*"),
+        many0_count(alt((
+            block_comment,
+            recognize(preceded(not(tag("*/")), anychar)),
+        ))),
+        tag("*/"),
+    ))(input)
+}
@@ -1,16 +1,20 @@
+use std::ops::Range;
+
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{char, none_of},
-    combinator::{not, peek, recognize},
-    multi::many0_count,
-    sequence::{delimited, terminated},
+    character::complete::{anychar, char, none_of},
+    combinator::{map, not, peek, recognize},
+    multi::{fold_many0, many0_count},
+    sequence::{delimited, pair, preceded, terminated},
     InputTake,
 };
 
 use super::{
-    combinator::cond_err,
-    literal::{char_lit, str_lit},
+    combinator::{cond_err, ws},
+    comment::{block_comment, line_comment},
+    ident::{identifier_or_keyword, keyword},
+    literal::{char_lit, raw_byte_str_lit, raw_str_lit, str_lit},
     NomResult,
 };
 
@@ -24,10 +28,13 @@ pub fn group<'a>(start_delim: char, end_delim: char) -> impl FnMut(&'a str) -> N
     }
 }
 
-// TODO: handle comments
 fn expr_impl<'a>(eager_brace: bool, nested_expr: bool) -> impl FnMut(&'a str) -> NomResult<&str> {
     move |i| {
         recognize(many0_count(alt((
+            line_comment,
+            block_comment,
+            raw_str_lit,
+            raw_byte_str_lit,
             str_lit,
             char_lit,
             group('(', ')'),
@@ -44,3 +51,105 @@ fn expr_impl<'a>(eager_brace: bool, nested_expr: bool) -> impl FnMut(&'a str) ->
 pub fn expr<'a>(eager_brace: bool) -> impl FnMut(&'a str) -> NomResult<&str> {
     move |i| expr_impl(eager_brace, false)(i).map(|(_, o)| i.take_split(o.trim_end().len()))
 }
+
+pub fn ends_with_line_comment(input: &str) -> bool {
+    let token = alt((
+        map(line_comment, |_| true),
+        map(
+            alt((
+                block_comment,
+                raw_str_lit,
+                raw_byte_str_lit,
+                str_lit,
+                char_lit,
+                recognize(anychar),
+            )),
+            |_| false,
+        ),
+    ));
+
+    fold_many0(token, || false, |_, is_comment| is_comment)(input)
+        .map(|(_, is_comment)| is_comment)
+        .unwrap_or_default()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Token {
+    Comment,
+    Literal,
+    Other,
+}
+
+fn token(input: &str) -> NomResult<'_, (Token, &str)> {
+    alt((
+        map(alt((line_comment, block_comment)), |s| (Token::Comment, s)),
+        map(
+            alt((
+                raw_str_lit,
+                raw_byte_str_lit,
+                recognize(str_lit),
+                recognize(char_lit),
+            )),
+            |s| (Token::Literal, s),
+        ),
+        map(alt((identifier_or_keyword, recognize(anychar))), |s| {
+            (Token::Other, s)
+        }),
+    ))(input)
+}
+
+fn any_token(input: &str, predicate: impl Fn(Token, &str) -> bool) -> bool {
+    let mut rest = input;
+
+    while let Ok((r, (kind, s))) = token(rest) {
+        if predicate(kind, s) {
+            return true;
+        }
+
+        rest = r;
+    }
+
+    false
+}
+
+pub fn has_comments(input: &str) -> bool {
+    any_token(input, |kind, _| kind == Token::Comment)
+}
+
+pub fn has_multiline_literal(input: &str) -> bool {
+    any_token(input, |kind, s| kind == Token::Literal && s.contains('\n'))
+}
+
+pub fn nested_macro_bodies(input: &str) -> Vec<Range<usize>> {
+    let mut bodies = Vec::new();
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        if let Ok((r, body)) = preceded(pair(keyword("html"), ws(char('!'))), group('{', '}'))(rest)
+        {
+            let start =
+                input.len() - rest.len() + (body.as_ptr() as usize - rest.as_ptr() as usize);
+            bodies.push(start..start + body.len());
+            rest = r;
+            continue;
+        }
+
+        let token = alt((
+            line_comment,
+            block_comment,
+            raw_str_lit,
+            raw_byte_str_lit,
+            recognize(str_lit),
+            recognize(char_lit),
+            identifier_or_keyword,
+            recognize(anychar),
+        ))(rest);
+
+        match token {
+            Ok((r, _)) => rest = r,
+            Err(_) => break,
+        }
+    }
+
+    bodies
+}
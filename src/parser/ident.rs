@@ -1,6 +1,6 @@
 use nom::{
-    bytes::complete::{tag, take_while, take_while1},
-    combinator::{map_parser, recognize, verify},
+    bytes::complete::{take_while, take_while1},
+    combinator::{recognize, verify},
     sequence::pair,
     AsChar,
 };
@@ -15,17 +15,21 @@ pub const KEYWORDS: [&str; 51] = [
     "override", "priv", "typeof", "unsized", "virtual", "yield", "try",
 ];
 
-fn identifier_impl(input: &str) -> NomResult<&str> {
+fn identifier_impl(input: &str) -> NomResult<'_, &str> {
     recognize(pair(
         take_while1(|c: char| c.is_alpha() || c == '_' || c >= '\u{0080}'),
         take_while(|c: char| c.is_alphanum() || c == '_' || c >= '\u{0080}'),
     ))(input)
 }
 
-pub fn identifier(input: &str) -> NomResult<&str> {
+pub fn identifier_or_keyword(input: &str) -> NomResult<'_, &str> {
+    identifier_impl(input)
+}
+
+pub fn identifier(input: &str) -> NomResult<'_, &str> {
     verify(identifier_impl, |s| !KEYWORDS.contains(s))(input)
 }
 
-pub fn keyword<'a>(kw: &'a str) -> impl FnMut(&'a str) -> NomResult<&str> {
-    map_parser(identifier_impl, tag(kw))
+pub fn keyword<'a>(kw: &'a str) -> impl FnMut(&'a str) -> NomResult<'a, &'a str> {
+    verify(identifier_impl, move |s: &str| s == kw)
 }
@@ -1,6 +1,6 @@
 use nom::{
     branch::alt,
-    bytes::complete::{escaped, tag, take_while, take_while1},
+    bytes::complete::{escaped, tag, take_until, take_while, take_while1},
     character::complete::{anychar, char, digit1, none_of, one_of},
     combinator::{map, opt, peek, recognize, verify},
     sequence::{delimited, pair, preceded, terminated, tuple},
@@ -9,11 +9,11 @@ use nom::{
 
 use super::{ident::keyword, NomResult};
 
-pub fn bool_lit(input: &str) -> NomResult<&str> {
+pub fn bool_lit(input: &str) -> NomResult<'_, &str> {
     alt((keyword("true"), keyword("false")))(input)
 }
 
-pub fn char_lit(input: &str) -> NomResult<&str> {
+pub fn char_lit(input: &str) -> NomResult<'_, &str> {
     map(
         delimited(
             char('\''),
@@ -24,11 +24,11 @@ pub fn char_lit(input: &str) -> NomResult<&str> {
     )(input)
 }
 
-pub fn byte_lit(input: &str) -> NomResult<&str> {
+pub fn byte_lit(input: &str) -> NomResult<'_, &str> {
     preceded(char('b'), char_lit)(input)
 }
 
-pub fn str_lit(input: &str) -> NomResult<&str> {
+pub fn str_lit(input: &str) -> NomResult<'_, &str> {
     map(
         delimited(
             char('"'),
@@ -39,10 +39,26 @@ pub fn str_lit(input: &str) -> NomResult<&str> {
     )(input)
 }
 
-pub fn byte_str_lit(input: &str) -> NomResult<&str> {
+pub fn byte_str_lit(input: &str) -> NomResult<'_, &str> {
     preceded(char('b'), str_lit)(input)
 }
 
+pub fn raw_str_lit(input: &str) -> NomResult<'_, &str> {
+    let (rest, hashes) = preceded(char('r'), take_while(|c| c == '#'))(input)?;
+    let terminator = format!("\"{}", hashes);
+
+    let (rest, _) = terminated(
+        preceded(char('"'), take_until(terminator.as_str())),
+        tag(terminator.as_str()),
+    )(rest)?;
+
+    Ok((rest, &input[..input.len() - rest.len()]))
+}
+
+pub fn raw_byte_str_lit(input: &str) -> NomResult<'_, &str> {
+    recognize(preceded(char('b'), raw_str_lit))(input)
+}
+
 pub fn int_digits(radix: u32) -> impl FnMut(&str) -> NomResult<&str> {
     move |i| {
         recognize(tuple((
@@ -53,14 +69,14 @@ pub fn int_digits(radix: u32) -> impl FnMut(&str) -> NomResult<&str> {
     }
 }
 
-fn dec_lit(input: &str) -> NomResult<&str> {
+fn dec_lit(input: &str) -> NomResult<'_, &str> {
     recognize(pair(
         digit1,
         take_while(|c: char| c.is_dec_digit() || c == '_'),
     ))(input)
 }
 
-pub fn int_lit(input: &str) -> NomResult<&str> {
+pub fn int_lit(input: &str) -> NomResult<'_, &str> {
     let int_suffix = alt((
         tag("i8"),
         tag("i16"),
@@ -92,7 +108,7 @@ pub fn int_lit(input: &str) -> NomResult<&str> {
     ))(input)
 }
 
-pub fn float_lit(input: &str) -> NomResult<&str> {
+pub fn float_lit(input: &str) -> NomResult<'_, &str> {
     let float_suffix = |i| alt((tag("f32"), tag("f64")))(i);
     let float_exp = |i| recognize(tuple((one_of("eE"), opt(one_of("+-")), int_digits(10))))(i);
 
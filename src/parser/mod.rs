@@ -3,15 +3,18 @@ use std::ops::Range;
 use miette::SourceSpan;
 use nom::{
     branch::alt,
-    bytes::complete::tag,
-    character::complete::{alpha1, alphanumeric1, char, multispace0, multispace1, not_line_ending},
-    combinator::{all_consuming, cut, map, opt, recognize, value},
-    multi::{many0, separated_list0},
+    bytes::complete::{tag, take_while1},
+    character::complete::{char, multispace0, not_line_ending, one_of},
+    combinator::{
+        all_consuming, consumed, cut, map, not, opt, peek, recognize, success, value, verify,
+    },
+    multi::many0,
     sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
-    Finish,
+    Finish, Offset,
 };
 
 mod combinator;
+mod comment;
 mod error;
 mod expr;
 mod ident;
@@ -20,19 +23,30 @@ mod path;
 mod pattern;
 
 use combinator::ws;
+use comment::block_comment;
 use error::ParserError;
-use expr::{expr, group};
-use ident::keyword;
-use literal::str_lit;
+pub use expr::{ends_with_line_comment, has_comments, has_multiline_literal};
+use expr::{expr, group, nested_macro_bodies};
+use ident::{identifier_or_keyword, keyword};
+use literal::{raw_str_lit, str_lit};
 use pattern::pattern;
 
 use crate::error::ParseError;
 
 pub type NomResult<'a, O> = Result<(&'a str, O), nom::Err<ParserError<&'a str>>>;
 
+#[derive(Clone, Debug)]
+pub enum Comment<'a> {
+    Line(&'a str),
+    Block(&'a str),
+}
+
 #[derive(Clone, Debug)]
 pub struct MatchArm<'a> {
+    pub comments: Vec<Comment<'a>>,
     pub pattern: &'a str,
+    pub guard: Option<&'a str>,
+    pub body_comments: Vec<Comment<'a>>,
     pub body: Node<'a>,
 }
 
@@ -40,6 +54,7 @@ pub struct MatchArm<'a> {
 pub struct Match<'a> {
     pub scrut: &'a str,
     pub arms: Vec<MatchArm<'a>>,
+    pub trailing_comments: Vec<Comment<'a>>,
 }
 
 #[derive(Clone, Debug)]
@@ -54,6 +69,24 @@ pub struct For<'a> {
     pub body: Block<'a>,
 }
 
+#[derive(Clone, Debug)]
+pub struct LetCondition<'a> {
+    pub pattern: &'a str,
+    pub expr: &'a str,
+}
+
+#[derive(Clone, Debug)]
+pub enum Condition<'a> {
+    Expr(&'a str),
+    Let(LetCondition<'a>),
+}
+
+#[derive(Clone, Debug)]
+pub struct While<'a> {
+    pub cond: Condition<'a>,
+    pub body: Block<'a>,
+}
+
 #[derive(Clone, Debug)]
 pub enum Else<'a> {
     If(If<'a>),
@@ -62,8 +95,10 @@ pub enum Else<'a> {
 
 #[derive(Clone, Debug)]
 pub struct If<'a> {
-    pub cond: &'a str,
+    pub cond: Condition<'a>,
     pub body: Block<'a>,
+    pub else_comments: Vec<Comment<'a>>,
+    pub else_body_comments: Vec<Comment<'a>>,
     pub else_clause: Option<Box<Else<'a>>>,
 }
 
@@ -71,6 +106,7 @@ pub struct If<'a> {
 pub enum ControlStructure<'a> {
     If(If<'a>),
     For(For<'a>),
+    While(While<'a>),
     Let(Let<'a>),
     Match(Match<'a>),
 }
@@ -82,8 +118,7 @@ pub struct Splice<'a> {
 
 #[derive(Clone, Debug)]
 pub struct Block<'a> {
-    pub newline: bool,
-    pub nodes: Vec<Node<'a>>,
+    pub nodes: Vec<Child<'a>>,
 }
 
 #[derive(Clone, Debug)]
@@ -92,72 +127,137 @@ pub enum ElementBody<'a> {
     Block(Block<'a>),
 }
 
+#[derive(Clone, Debug)]
+pub struct Toggle<'a> {
+    pub cond: &'a str,
+}
+
+#[derive(Clone, Debug)]
+pub struct Optional<'a> {
+    pub expr: &'a str,
+}
+
 #[derive(Clone, Debug)]
 pub enum AttributeValue<'a> {
     String(&'a str),
+    Splice(Splice<'a>),
+    Block(Block<'a>),
+    Toggle(Toggle<'a>),
+    Optional(Optional<'a>),
     Empty,
 }
 
 #[derive(Clone, Debug)]
 pub struct Attribute<'a> {
-    pub name: &'a str,
+    pub name: Name<'a>,
     pub value: AttributeValue<'a>,
 }
 
+#[derive(Clone, Debug)]
+pub enum Name<'a> {
+    Bare(&'a str),
+    Quoted(&'a str),
+}
+
+#[derive(Clone, Debug)]
+pub enum Shorthand<'a> {
+    Class(Name<'a>, Option<Toggle<'a>>),
+    Id(Name<'a>),
+}
+
+#[derive(Clone, Debug)]
+pub enum AttrItem<'a> {
+    Shorthand(Shorthand<'a>),
+    Attribute(Attribute<'a>),
+    Comment(Comment<'a>),
+}
+
 #[derive(Clone, Debug)]
 pub struct Element<'a> {
-    pub name: &'a str,
-    pub attrs: Vec<Attribute<'a>>,
+    pub name: Option<Name<'a>>,
+    pub attrs: Vec<AttrItem<'a>>,
     pub body: ElementBody<'a>,
 }
 
+#[derive(Clone, Debug)]
+pub struct BlockComment<'a> {
+    pub text: &'a str,
+    pub newline: bool,
+}
+
 #[derive(Clone, Debug)]
 pub enum Node<'a> {
     Element(Element<'a>),
     Block(Block<'a>),
     StrLit(&'a str),
+    RawStrLit(&'a str),
     Comment(&'a str),
+    BlockComment(BlockComment<'a>),
     Splice(Splice<'a>),
     ControlStructure(ControlStructure<'a>),
 }
 
+#[derive(Clone, Debug)]
+pub struct Child<'a> {
+    /// Number of blank lines between this node and the previous one
+    pub blank_lines: usize,
+    pub node: Node<'a>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Markup<'a> {
-    pub nodes: Vec<Node<'a>>,
+    pub nodes: Vec<Child<'a>>,
 }
 
-fn match_arms(input: &str) -> NomResult<Vec<MatchArm>> {
+fn match_guard(input: &str) -> NomResult<'_, &str> {
+    preceded(keyword("if"), ws(expr(true)))(input)
+}
+
+fn match_arms(input: &str) -> NomResult<'_, Vec<MatchArm<'_>>> {
     many0(map(
-        ws(separated_pair(
-            expr(true),
+        ws(tuple((
+            comments,
+            pattern,
+            opt(preceded(multispace0, match_guard)),
             ws(tag("=>")),
+            comments,
             terminated(node, opt(char(','))),
-        )),
-        |(pattern, body)| MatchArm { pattern, body },
+        ))),
+        |(comments, pattern, guard, _, body_comments, body)| MatchArm {
+            comments,
+            pattern,
+            guard,
+            body_comments,
+            body,
+        },
     ))(input)
 }
 
-fn match_expr(input: &str) -> NomResult<Match> {
+fn match_expr(input: &str) -> NomResult<'_, Match<'_>> {
     preceded(
         keyword("match"),
         map(
             pair(
                 ws(expr(false)),
-                delimited(char('{'), ws(match_arms), char('}')),
+                delimited(char('{'), ws(pair(match_arms, comments)), char('}')),
             ),
-            |(scrut, arms)| Match { scrut, arms },
+            |(scrut, (arms, trailing_comments))| Match {
+                scrut,
+                arms,
+                trailing_comments,
+            },
         ),
     )(input)
 }
 
-fn let_expr(input: &str) -> NomResult<Let> {
+fn let_expr(input: &str) -> NomResult<'_, Let<'_>> {
     preceded(
         keyword("let"),
         map(terminated(ws(expr(true)), char(';')), |expr| Let { expr }),
     )(input)
 }
 
-fn for_expr(input: &str) -> NomResult<For> {
+fn for_expr(input: &str) -> NomResult<'_, For<'_>> {
     preceded(
         keyword("for"),
         map(
@@ -171,128 +271,332 @@ fn for_expr(input: &str) -> NomResult<For> {
     )(input)
 }
 
-fn else_expr(input: &str) -> NomResult<Else> {
+fn let_condition(input: &str) -> NomResult<'_, LetCondition<'_>> {
+    preceded(
+        keyword("let"),
+        map(
+            separated_pair(ws(pattern), char('='), ws(expr(false))),
+            |(pattern, expr)| LetCondition { pattern, expr },
+        ),
+    )(input)
+}
+
+fn condition(input: &str) -> NomResult<'_, Condition<'_>> {
+    alt((
+        map(let_condition, Condition::Let),
+        map(expr(false), Condition::Expr),
+    ))(input)
+}
+
+fn while_expr(input: &str) -> NomResult<'_, While<'_>> {
+    preceded(
+        keyword("while"),
+        map(pair(ws(condition), block), |(cond, body)| While {
+            cond,
+            body,
+        }),
+    )(input)
+}
+
+fn else_expr(input: &str) -> NomResult<'_, (Vec<Comment<'_>>, Box<Else<'_>>)> {
     preceded(
         keyword("else"),
-        ws(alt((map(if_expr, Else::If), map(block, Else::Then)))),
+        pair(
+            comments,
+            map(
+                ws(alt((map(if_expr, Else::If), map(block, Else::Then)))),
+                Box::new,
+            ),
+        ),
     )(input)
 }
 
-fn if_expr(input: &str) -> NomResult<If> {
-    let opt_else = opt(preceded(char('@'), map(else_expr, Box::new)));
+fn if_expr(input: &str) -> NomResult<'_, If<'_>> {
+    let opt_else = opt(pair(comments, preceded(char('@'), else_expr)));
 
     preceded(
         keyword("if"),
         map(
-            tuple((ws(expr(false)), block, ws(opt_else))),
-            |(cond, body, else_clause)| If {
-                cond,
-                body,
-                else_clause,
+            tuple((ws(condition), block, ws(opt_else))),
+            |(cond, body, else_clause)| {
+                let (else_comments, else_body_comments, else_clause) = match else_clause {
+                    Some((comments, (body_comments, else_clause))) => {
+                        (comments, body_comments, Some(else_clause))
+                    }
+                    None => (Vec::new(), Vec::new(), None),
+                };
+
+                If {
+                    cond,
+                    body,
+                    else_comments,
+                    else_body_comments,
+                    else_clause,
+                }
             },
         ),
     )(input)
 }
 
-fn control_structure(input: &str) -> NomResult<ControlStructure> {
+fn control_structure(input: &str) -> NomResult<'_, ControlStructure<'_>> {
     preceded(
         char('@'),
         cut(alt((
             map(if_expr, ControlStructure::If),
             map(for_expr, ControlStructure::For),
+            map(while_expr, ControlStructure::While),
             map(let_expr, ControlStructure::Let),
             map(match_expr, ControlStructure::Match),
         ))),
     )(input)
 }
 
-fn splice(input: &str) -> NomResult<Splice> {
+fn splice(input: &str) -> NomResult<'_, Splice<'_>> {
     map(group('(', ')'), |expr| Splice {
         expr: expr[1..expr.len() - 1].trim(),
     })(input)
 }
 
-fn block(input: &str) -> NomResult<Block> {
-    delimited(
-        char('{'),
-        map(pair(multispace0, ws(nodes)), |(whitespace, nodes)| Block {
+fn block(input: &str) -> NomResult<'_, Block<'_>> {
+    map(delimited(char('{'), ws(nodes), cut(char('}'))), |nodes| {
+        Block { nodes }
+    })(input)
+}
+
+fn comment(input: &str) -> NomResult<'_, &str> {
+    delimited(tag("//"), not_line_ending, multispace0)(input)
+}
+
+fn block_comment_node(input: &str) -> NomResult<'_, BlockComment<'_>> {
+    map(
+        pair(block_comment, peek(multispace0)),
+        |(text, whitespace)| BlockComment {
+            text,
             newline: whitespace.contains('\n'),
-            nodes,
-        }),
-        cut(char('}')),
+        },
     )(input)
 }
 
-fn comment(input: &str) -> NomResult<&str> {
-    delimited(tag("//"), not_line_ending, multispace0)(input)
+fn any_comment(input: &str) -> NomResult<'_, Comment<'_>> {
+    alt((
+        map(comment, Comment::Line),
+        map(block_comment, Comment::Block),
+    ))(input)
 }
 
-fn void(input: &str) -> NomResult<()> {
-    value((), char(';'))(input)
+fn comments(input: &str) -> NomResult<'_, Vec<Comment<'_>>> {
+    many0(ws(any_comment))(input)
 }
 
-fn body(input: &str) -> NomResult<ElementBody> {
-    cut(alt((
-        value(ElementBody::Void, void),
-        map(block, ElementBody::Block),
-    )))(input)
+fn void(input: &str) -> NomResult<'_, ()> {
+    value((), char(';'))(input)
 }
 
-fn non_empty_attribute(input: &str) -> NomResult<Attribute> {
-    map(separated_pair(tag_name, ws(char('=')), str_lit), |a| {
-        Attribute {
-            name: a.0,
-            value: AttributeValue::String(a.1),
+fn body<'a>(commit: bool) -> impl FnMut(&'a str) -> NomResult<'a, ElementBody<'a>> {
+    move |i| {
+        let mut body = alt((
+            value(ElementBody::Void, void),
+            map(block, ElementBody::Block),
+        ));
+
+        if commit {
+            cut(body)(i)
+        } else {
+            body(i)
         }
+    }
+}
+
+fn attribute_value(input: &str) -> NomResult<'_, AttributeValue<'_>> {
+    alt((
+        map(str_lit, AttributeValue::String),
+        map(splice, AttributeValue::Splice),
+        map(block, AttributeValue::Block),
+        map(optional, AttributeValue::Optional),
+    ))(input)
+}
+
+fn non_empty_attribute(input: &str) -> NomResult<'_, Attribute<'_>> {
+    map(
+        separated_pair(name, ws(char('=')), attribute_value),
+        |(name, value)| Attribute { name, value },
+    )(input)
+}
+
+fn toggle(input: &str) -> NomResult<'_, Toggle<'_>> {
+    map(group('[', ']'), |cond| Toggle {
+        cond: cond[1..cond.len() - 1].trim(),
     })(input)
 }
 
-fn empty_attribute(input: &str) -> NomResult<Attribute> {
-    map(tag_name, |a| Attribute {
-        name: a,
+fn optional(input: &str) -> NomResult<'_, Optional<'_>> {
+    map(group('[', ']'), |expr| Optional {
+        expr: expr[1..expr.len() - 1].trim(),
+    })(input)
+}
+
+fn toggled_attribute(input: &str) -> NomResult<'_, Attribute<'_>> {
+    map(
+        pair(name, preceded(multispace0, toggle)),
+        |(name, toggle)| Attribute {
+            name,
+            value: AttributeValue::Toggle(toggle),
+        },
+    )(input)
+}
+
+fn empty_attribute(input: &str) -> NomResult<'_, Attribute<'_>> {
+    map(name, |name| Attribute {
+        name,
         value: AttributeValue::Empty,
     })(input)
 }
 
-fn attrs(input: &str) -> NomResult<Vec<Attribute>> {
-    separated_list0(multispace1, alt((non_empty_attribute, empty_attribute)))(input)
+fn attribute(input: &str) -> NomResult<'_, Attribute<'_>> {
+    alt((non_empty_attribute, toggled_attribute, empty_attribute))(input)
 }
 
-fn tag_name(input: &str) -> NomResult<&str> {
+fn name_fragment(input: &str) -> NomResult<'_, &str> {
+    alt((
+        take_while1(|c: char| c.is_alphanumeric() || c == '_'),
+        recognize(str_lit),
+    ))(input)
+}
+
+fn html_name(input: &str) -> NomResult<'_, &str> {
     recognize(pair(
-        alpha1,
-        many0(alt((alphanumeric1, tag("_"), tag("-")))),
+        name_fragment,
+        many0(pair(one_of("-:"), opt(name_fragment))),
     ))(input)
 }
 
-fn element(input: &str) -> NomResult<Element> {
-    map(tuple((tag_name, ws(attrs), body)), |(name, attrs, body)| {
-        Element { name, attrs, body }
-    })(input)
+fn ident_fragment(input: &str) -> NomResult<'_, &str> {
+    verify(identifier_or_keyword, |s: &str| s != "_")(input)
+}
+
+/// Whether a quoted name can be written bare, digit-led fragments would become number literals
+pub fn is_html_name(name: &str) -> bool {
+    all_consuming(recognize(pair(
+        ident_fragment,
+        many0(pair(one_of("-:"), opt(ident_fragment))),
+    )))(name)
+    .is_ok()
 }
 
-fn node(input: &str) -> NomResult<Node> {
+fn quoted_name(input: &str) -> NomResult<'_, &str> {
+    terminated(str_lit, not(one_of("-:")))(input)
+}
+
+// Maud names only allow `-` and `:` between fragments, anything else has to be quoted
+fn name(input: &str) -> NomResult<'_, Name<'_>> {
+    alt((map(quoted_name, Name::Quoted), map(html_name, Name::Bare)))(input)
+}
+
+fn shorthand(input: &str) -> NomResult<'_, Shorthand<'_>> {
+    alt((
+        map(
+            preceded(char('.'), pair(name, opt(preceded(multispace0, toggle)))),
+            |(name, toggle)| Shorthand::Class(name, toggle),
+        ),
+        map(preceded(char('#'), name), Shorthand::Id),
+    ))(input)
+}
+
+fn attrs(input: &str) -> NomResult<'_, Vec<AttrItem<'_>>> {
+    many0(preceded(
+        multispace0,
+        alt((
+            map(shorthand, AttrItem::Shorthand),
+            map(attribute, AttrItem::Attribute),
+            map(any_comment, AttrItem::Comment),
+        )),
+    ))(input)
+}
+
+fn element_name(input: &str) -> NomResult<'_, Name<'_>> {
+    map(
+        recognize(pair(
+            alt((
+                identifier_or_keyword,
+                recognize(terminated(str_lit, peek(one_of("-:")))),
+            )),
+            many0(pair(one_of("-:"), opt(name_fragment))),
+        )),
+        Name::Bare,
+    )(input)
+}
+
+fn implicit_div_attrs(input: &str) -> NomResult<'_, Vec<AttrItem<'_>>> {
+    preceded(peek(one_of(".#")), attrs)(input)
+}
+
+fn element(input: &str) -> NomResult<'_, Element<'_>> {
+    // A quoted name may just be text followed by more markup, so it takes no attributes and
+    // is only an element if a body follows directly
+    if let Ok((rest, (name, body))) = separated_pair(quoted_name, multispace0, body(false))(input) {
+        return Ok((
+            rest,
+            Element {
+                name: Some(Name::Quoted(name)),
+                attrs: Vec::new(),
+                body,
+            },
+        ));
+    }
+
+    let (rest, (name, attrs)) = alt((
+        pair(map(element_name, Some), attrs),
+        pair(success(None), implicit_div_attrs),
+    ))(input)?;
+
+    let (rest, body) = preceded(multispace0, body(true))(rest)?;
+
+    Ok((rest, Element { name, attrs, body }))
+}
+
+fn node(input: &str) -> NomResult<'_, Node<'_>> {
     alt((
+        map(raw_str_lit, Node::RawStrLit),
         map(element, Node::Element),
         map(str_lit, Node::StrLit),
         map(comment, Node::Comment),
+        map(block_comment_node, Node::BlockComment),
         map(block, Node::Block),
         map(splice, Node::Splice),
         map(control_structure, Node::ControlStructure),
     ))(input)
 }
 
-fn nodes(input: &str) -> NomResult<Vec<Node>> {
-    many0(preceded(multispace0, node))(input)
+fn nodes(input: &str) -> NomResult<'_, Vec<Child<'_>>> {
+    many0(map(
+        preceded(multispace0, consumed(node)),
+        |(consumed, node)| {
+            // Whitespace is often consumed by the preceding node, so look back from where this one starts
+            let before = &input[..input.offset(consumed)];
+            let whitespace = &before[before.trim_end().len()..];
+
+            Child {
+                blank_lines: whitespace.matches('\n').count().saturating_sub(1),
+                node,
+            }
+        },
+    ))(input)
 }
 
-fn markup(input: &str) -> Result<Markup, ParserError<&str>> {
+fn markup(input: &str) -> Result<Markup<'_>, ParserError<&str>> {
     all_consuming(map(nodes, |n| Markup { nodes: n }))(input)
         .finish()
         .map(|(_, markup)| markup)
 }
 
-pub fn parse_range(src: &str, range: Range<usize>) -> Result<Markup, ParseError> {
+pub fn nested_markup(expr: &str) -> Vec<(Range<usize>, Markup<'_>)> {
+    nested_macro_bodies(expr)
+        .into_iter()
+        .filter_map(|range| Some((range.clone(), markup(&expr[range]).ok()?)))
+        .collect()
+}
+
+pub fn parse_range(src: &str, range: Range<usize>) -> Result<Markup<'_>, ParseError> {
     let content = src[range].trim();
 
     markup(content).map_err(|e| {
@@ -305,3 +609,58 @@ pub fn parse_range(src: &str, range: Range<usize>) -> Result<Markup, ParseError>
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_keyword_path_patterns() {
+        for arm in [
+            "Self::A",
+            "self::A",
+            "crate::Kind::B",
+            "super::Kind::C(x)",
+            "Self { a, .. }",
+        ] {
+            let src = format!(r#"@match self {{ {} => "a", _ => "b" }}"#, arm);
+            assert!(markup(&src).is_ok(), "{}", arm);
+        }
+    }
+
+    #[test]
+    fn parses_comments_in_tags() {
+        let markup = markup(
+            r#"p /* c */ { "a" } input type="text" // why
+            ;"#,
+        )
+        .unwrap();
+
+        for child in &markup.nodes {
+            let Node::Element(element) = &child.node else {
+                panic!("expected an element");
+            };
+
+            assert!(matches!(element.attrs.last(), Some(AttrItem::Comment(_))));
+        }
+    }
+    #[test]
+    fn unquotes_identifier_names_only() {
+        assert!(is_html_name("data-id"));
+        assert!(is_html_name("xml:lang"));
+        assert!(is_html_name("type"));
+        assert!(!is_html_name("1abc"));
+        assert!(!is_html_name("data-2x"));
+        assert!(!is_html_name("a.b"));
+        assert!(!is_html_name("_"));
+    }
+    #[test]
+    fn scans_comments_outside_literals() {
+        assert!(has_comments("a // b"));
+        assert!(has_comments("a /* b */ + c"));
+        assert!(!has_comments(r#"link("https://x", a)"#));
+        assert!(!has_comments(r##"r#"/* not a comment */"#"##));
+        assert!(has_multiline_literal("f(\"a\nb\")"));
+        assert!(!has_multiline_literal("f(\"a\",\n b)"));
+    }
+}
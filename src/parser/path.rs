@@ -7,9 +7,14 @@ use nom::{
     sequence::{pair, preceded, terminated, tuple},
 };
 
-use super::{combinator::ws, expr::group, ident::identifier, NomResult};
+use super::{
+    combinator::ws,
+    expr::group,
+    ident::{identifier, keyword},
+    NomResult,
+};
 
-pub fn simple_path(input: &str) -> NomResult<&str> {
+pub fn simple_path(input: &str) -> NomResult<'_, &str> {
     recognize(tuple((
         opt(terminated(tag("::"), multispace0)),
         simple_path_segment,
@@ -17,15 +22,21 @@ pub fn simple_path(input: &str) -> NomResult<&str> {
     )))(input)
 }
 
-fn simple_path_segment(input: &str) -> NomResult<&str> {
-    alt((identifier, tag("$crate")))(input)
+fn simple_path_segment(input: &str) -> NomResult<'_, &str> {
+    alt((
+        identifier,
+        keyword("super"),
+        keyword("self"),
+        keyword("crate"),
+        tag("$crate"),
+    ))(input)
 }
 
-pub fn path_expression(input: &str) -> NomResult<&str> {
+pub fn path_expression(input: &str) -> NomResult<'_, &str> {
     alt((path_in_expression, qualified_path_in_expression))(input)
 }
 
-pub fn path_in_expression(input: &str) -> NomResult<&str> {
+pub fn path_in_expression(input: &str) -> NomResult<'_, &str> {
     recognize(tuple((
         opt(terminated(tag("::"), multispace0)),
         path_expr_segment,
@@ -33,28 +44,35 @@ pub fn path_in_expression(input: &str) -> NomResult<&str> {
     )))(input)
 }
 
-fn path_expr_segment(input: &str) -> NomResult<&str> {
+fn path_expr_segment(input: &str) -> NomResult<'_, &str> {
     recognize(pair(
         path_ident_segment,
         opt(preceded(ws(tag("::")), generic_args)),
     ))(input)
 }
 
-fn path_ident_segment(input: &str) -> NomResult<&str> {
-    alt((identifier, tag("$crate")))(input)
+fn path_ident_segment(input: &str) -> NomResult<'_, &str> {
+    alt((
+        identifier,
+        keyword("super"),
+        keyword("self"),
+        keyword("Self"),
+        keyword("crate"),
+        tag("$crate"),
+    ))(input)
 }
 
-fn generic_args(input: &str) -> NomResult<&str> {
+fn generic_args(input: &str) -> NomResult<'_, &str> {
     group('<', '>')(input)
 }
 
-fn qualified_path_in_expression(input: &str) -> NomResult<&str> {
+fn qualified_path_in_expression(input: &str) -> NomResult<'_, &str> {
     recognize(pair(
         qualified_path_type,
         many0(preceded(ws(tag("::")), path_expr_segment)),
     ))(input)
 }
 
-fn qualified_path_type(input: &str) -> NomResult<&str> {
+fn qualified_path_type(input: &str) -> NomResult<'_, &str> {
     group('<', '>')(input)
 }
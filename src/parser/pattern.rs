@@ -11,12 +11,15 @@ use super::{
     combinator::ws,
     expr::group,
     ident::{identifier, keyword},
-    literal::{bool_lit, byte_lit, byte_str_lit, char_lit, float_lit, int_lit, str_lit},
+    literal::{
+        bool_lit, byte_lit, byte_str_lit, char_lit, float_lit, int_lit, raw_byte_str_lit,
+        raw_str_lit, str_lit,
+    },
     path::{path_expression, path_in_expression, simple_path},
     NomResult,
 };
 
-pub fn pattern(input: &str) -> NomResult<&str> {
+pub fn pattern(input: &str) -> NomResult<'_, &str> {
     recognize(tuple((
         opt(terminated(char('|'), multispace0)),
         pattern_no_top_alt,
@@ -24,11 +27,11 @@ pub fn pattern(input: &str) -> NomResult<&str> {
     )))(input)
 }
 
-fn pattern_no_top_alt(input: &str) -> NomResult<&str> {
+fn pattern_no_top_alt(input: &str) -> NomResult<'_, &str> {
     alt((range_pattern, pattern_without_range))(input)
 }
 
-fn range_pattern(input: &str) -> NomResult<&str> {
+fn range_pattern(input: &str) -> NomResult<'_, &str> {
     recognize(tuple((
         opt(terminated(range_pattern_bound, multispace0)),
         tag(".."),
@@ -37,7 +40,7 @@ fn range_pattern(input: &str) -> NomResult<&str> {
     )))(input)
 }
 
-fn range_pattern_bound(input: &str) -> NomResult<&str> {
+fn range_pattern_bound(input: &str) -> NomResult<'_, &str> {
     recognize(alt((
         char_lit,
         byte_lit,
@@ -46,8 +49,9 @@ fn range_pattern_bound(input: &str) -> NomResult<&str> {
     )))(input)
 }
 
-fn pattern_without_range(input: &str) -> NomResult<&str> {
+fn pattern_without_range(input: &str) -> NomResult<'_, &str> {
     alt((
+        binding_pattern,
         wildcard_pattern,
         rest_pattern,
         literal_pattern,
@@ -63,11 +67,12 @@ fn pattern_without_range(input: &str) -> NomResult<&str> {
     ))(input)
 }
 
-// TODO: missing raw string variants
-fn literal_pattern(input: &str) -> NomResult<&str> {
+fn literal_pattern(input: &str) -> NomResult<'_, &str> {
     recognize(alt((
         char_lit,
         byte_lit,
+        raw_str_lit,
+        raw_byte_str_lit,
         str_lit,
         byte_str_lit,
         bool_lit,
@@ -75,7 +80,7 @@ fn literal_pattern(input: &str) -> NomResult<&str> {
     )))(input)
 }
 
-fn identifier_pattern(input: &str) -> NomResult<&str> {
+fn identifier_pattern(input: &str) -> NomResult<'_, &str> {
     recognize(tuple((
         many0(terminated(
             alt((keyword("ref"), keyword("mut"))),
@@ -86,15 +91,27 @@ fn identifier_pattern(input: &str) -> NomResult<&str> {
     )))(input)
 }
 
-fn wildcard_pattern(input: &str) -> NomResult<&str> {
+// `x @ ..` has to be tried before `path_pattern`, which would otherwise claim the identifier
+fn binding_pattern(input: &str) -> NomResult<'_, &str> {
+    recognize(tuple((
+        many0(terminated(
+            alt((keyword("ref"), keyword("mut"))),
+            multispace0,
+        )),
+        identifier,
+        preceded(ws(char('@')), pattern_no_top_alt),
+    )))(input)
+}
+
+fn wildcard_pattern(input: &str) -> NomResult<'_, &str> {
     tag("_")(input)
 }
 
-fn rest_pattern(input: &str) -> NomResult<&str> {
+fn rest_pattern(input: &str) -> NomResult<'_, &str> {
     tag("..")(input)
 }
 
-fn reference_pattern(input: &str) -> NomResult<&str> {
+fn reference_pattern(input: &str) -> NomResult<'_, &str> {
     recognize(tuple((
         alt((tag("&&"), tag("&"))),
         ws(opt(keyword("mut"))),
@@ -102,37 +119,37 @@ fn reference_pattern(input: &str) -> NomResult<&str> {
     )))(input)
 }
 
-fn struct_pattern(input: &str) -> NomResult<&str> {
+fn struct_pattern(input: &str) -> NomResult<'_, &str> {
     recognize(pair(
         path_in_expression,
         preceded(multispace0, group('{', '}')),
     ))(input)
 }
 
-fn tuple_struct_pattern(input: &str) -> NomResult<&str> {
+fn tuple_struct_pattern(input: &str) -> NomResult<'_, &str> {
     recognize(pair(
         path_in_expression,
         preceded(multispace0, group('(', ')')),
     ))(input)
 }
 
-fn tuple_pattern(input: &str) -> NomResult<&str> {
+fn tuple_pattern(input: &str) -> NomResult<'_, &str> {
     group('(', ')')(input)
 }
 
-fn grouped_pattern(input: &str) -> NomResult<&str> {
+fn grouped_pattern(input: &str) -> NomResult<'_, &str> {
     group('(', ')')(input)
 }
 
-fn slice_pattern(input: &str) -> NomResult<&str> {
+fn slice_pattern(input: &str) -> NomResult<'_, &str> {
     group('[', ']')(input)
 }
 
-fn path_pattern(input: &str) -> NomResult<&str> {
+fn path_pattern(input: &str) -> NomResult<'_, &str> {
     path_expression(input)
 }
 
-fn macro_invocation(input: &str) -> NomResult<&str> {
+fn macro_invocation(input: &str) -> NomResult<'_, &str> {
     recognize(separated_pair(
         simple_path,
         ws(char('!')),
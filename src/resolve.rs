@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+
+use syn::{Item, ItemUse, Stmt, UseTree};
+
+const DEFAULT_MACRO: &str = "maud::html";
+
+const FOREIGN_CRATES: [&str; 6] = [
+    "yew",
+    "dioxus",
+    "leptos",
+    "sycamore",
+    "typed_html",
+    "html_node",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MacroKind {
+    /// Resolves to a configured Maud macro
+    Maud,
+    /// Looks like `html!` but its origin is unknown, e.g. a bare `html!` without imports
+    Unresolved,
+    /// An `html!` dialect from another crate, e.g. `yew::html!`
+    Foreign,
+    Other,
+}
+
+fn split_path(path: &str) -> Vec<String> {
+    path.trim_start_matches("::")
+        .split("::")
+        .map(|segment| segment.trim().to_string())
+        .collect()
+}
+
+#[derive(Default)]
+struct Scope {
+    aliases: HashMap<String, Vec<Vec<String>>>,
+    globs: Vec<Vec<String>>,
+    /// Modules don't see the imports of their parent, blocks do
+    module: bool,
+}
+
+impl Scope {
+    fn new<'a>(uses: impl Iterator<Item = &'a ItemUse>, module: bool) -> Self {
+        let mut scope = Scope {
+            module,
+            ..Default::default()
+        };
+
+        for item in uses {
+            scope.add_use_tree(&item.tree, &mut Vec::new());
+        }
+
+        scope
+    }
+
+    fn add_alias(&mut self, name: String, path: Vec<String>) {
+        let paths = self.aliases.entry(name).or_default();
+
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+
+    fn add_use_tree(&mut self, tree: &UseTree, prefix: &mut Vec<String>) {
+        match tree {
+            UseTree::Path(path) => {
+                prefix.push(path.ident.to_string());
+                self.add_use_tree(&path.tree, prefix);
+                prefix.pop();
+            }
+            UseTree::Name(name) => {
+                let name = name.ident.to_string();
+
+                if name == "self" {
+                    if let Some(last) = prefix.last() {
+                        self.add_alias(last.clone(), prefix.clone());
+                    }
+                } else {
+                    let mut path = prefix.clone();
+                    path.push(name.clone());
+                    self.add_alias(name, path);
+                }
+            }
+            UseTree::Rename(rename) => {
+                let mut path = prefix.clone();
+
+                if rename.ident != "self" {
+                    path.push(rename.ident.to_string());
+                }
+
+                self.add_alias(rename.rename.to_string(), path);
+            }
+            UseTree::Glob(_) => self.globs.push(prefix.clone()),
+            UseTree::Group(group) => {
+                for tree in &group.items {
+                    self.add_use_tree(tree, prefix);
+                }
+            }
+        }
+    }
+}
+
+fn item_uses(items: &[Item]) -> impl Iterator<Item = &ItemUse> {
+    items.iter().filter_map(|item| match item {
+        Item::Use(item) => Some(item),
+        _ => None,
+    })
+}
+
+pub struct MacroResolver {
+    targets: Vec<Vec<String>>,
+    scopes: Vec<Scope>,
+}
+
+impl MacroResolver {
+    pub fn new(macros: &[String]) -> Self {
+        let targets = std::iter::once(DEFAULT_MACRO)
+            .chain(macros.iter().map(String::as_str))
+            .map(split_path)
+            .collect();
+
+        MacroResolver {
+            targets,
+            scopes: Vec::new(),
+        }
+    }
+
+    pub fn enter_module(&mut self, items: &[Item]) {
+        self.scopes.push(Scope::new(item_uses(items), true));
+    }
+
+    pub fn enter_block(&mut self, stmts: &[Stmt]) {
+        let uses = stmts.iter().filter_map(|stmt| match stmt {
+            Stmt::Item(Item::Use(item)) => Some(item),
+            _ => None,
+        });
+
+        self.scopes.push(Scope::new(uses, false));
+    }
+
+    pub fn exit(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Innermost scope first, up to the enclosing module
+    fn visible_scopes(&self) -> impl Iterator<Item = &Scope> {
+        let module = self.scopes.iter().rposition(|scope| scope.module);
+        self.scopes[module.unwrap_or_default()..].iter().rev()
+    }
+
+    pub fn resolve(&self, path: &[String]) -> Vec<Vec<String>> {
+        let Some((first, rest)) = path.split_first() else {
+            return vec![Vec::new()];
+        };
+
+        // A leading `::` always refers to an external crate
+        if first.is_empty() {
+            return vec![rest.to_vec()];
+        }
+
+        let aliases = self
+            .visible_scopes()
+            .find_map(|scope| scope.aliases.get(first));
+
+        if let Some(aliases) = aliases {
+            return aliases
+                .iter()
+                .map(|alias| alias.iter().chain(rest).cloned().collect())
+                .collect();
+        }
+
+        if rest.is_empty() {
+            let from_glob = self
+                .visible_scopes()
+                .flat_map(|scope| &scope.globs)
+                .find_map(|glob| {
+                    let mut candidate = glob.clone();
+                    candidate.push(first.clone());
+                    self.targets.contains(&candidate).then_some(candidate)
+                });
+
+            if let Some(candidate) = from_glob {
+                return vec![candidate];
+            }
+        }
+
+        vec![path.to_vec()]
+    }
+
+    pub fn classify(&self, path: &[String]) -> MacroKind {
+        let kinds: Vec<MacroKind> = self
+            .resolve(path)
+            .iter()
+            .map(|resolved| self.classify_resolved(resolved))
+            .collect();
+
+        match kinds.as_slice() {
+            [kind] => *kind,
+            // Conflicting imports, e.g. behind different cfgs, are formatted without failing on errors
+            kinds if kinds.contains(&MacroKind::Maud) => MacroKind::Unresolved,
+            kinds => kinds[0],
+        }
+    }
+
+    fn classify_resolved(&self, resolved: &[String]) -> MacroKind {
+        if self.targets.iter().any(|target| target == resolved) {
+            return MacroKind::Maud;
+        }
+
+        let Some(first) = resolved.first() else {
+            return MacroKind::Other;
+        };
+
+        if resolved.last().is_some_and(|name| name == "html") {
+            let local = matches!(first.as_str(), "crate" | "self" | "super");
+
+            let foreign_glob = self
+                .visible_scopes()
+                .flat_map(|scope| &scope.globs)
+                .any(|glob| {
+                    glob.first()
+                        .is_some_and(|c| FOREIGN_CRATES.contains(&c.as_str()))
+                });
+
+            return match resolved.len() {
+                1 if foreign_glob => MacroKind::Foreign,
+                1 => MacroKind::Unresolved,
+                _ if local => MacroKind::Unresolved,
+                _ => MacroKind::Foreign,
+            };
+        }
+
+        if FOREIGN_CRATES.contains(&first.as_str()) {
+            MacroKind::Foreign
+        } else {
+            MacroKind::Other
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::visit::Visit;
+
+    use super::*;
+    use crate::visitor::MacroVisitor;
+
+    fn path(path: &str) -> Vec<String> {
+        split_path(path)
+    }
+
+    fn file_resolver(code: &str, macros: &[&str]) -> MacroResolver {
+        let file = syn::parse_file(code).unwrap();
+        let macros: Vec<String> = macros.iter().map(|m| m.to_string()).collect();
+
+        let mut resolver = MacroResolver::new(&macros);
+        resolver.enter_module(&file.items);
+        resolver
+    }
+
+    /// Start lines and strictness of every macro that would be formatted
+    fn formatted(code: &str) -> Vec<(usize, bool)> {
+        let mut visitor = MacroVisitor {
+            resolver: MacroResolver::new(&[]),
+            locations: Vec::new(),
+        };
+
+        visitor.visit_file(&syn::parse_file(code).unwrap());
+
+        visitor
+            .locations
+            .iter()
+            .map(|location| (location.start_line, location.strict))
+            .collect()
+    }
+
+    #[test]
+    fn classifies_imports() {
+        let resolver = file_resolver("use maud::html;", &[]);
+        assert_eq!(resolver.classify(&path("html")), MacroKind::Maud);
+        assert_eq!(resolver.classify(&path("maud::html")), MacroKind::Maud);
+        assert_eq!(resolver.classify(&path("yew::html")), MacroKind::Foreign);
+        assert_eq!(resolver.classify(&path("format")), MacroKind::Other);
+
+        let resolver = file_resolver("use maud::html as markup;", &[]);
+        assert_eq!(resolver.classify(&path("markup")), MacroKind::Maud);
+
+        let resolver = file_resolver("use maud::{self, html};", &[]);
+        assert_eq!(resolver.classify(&path("maud::html")), MacroKind::Maud);
+        assert_eq!(resolver.classify(&path("html")), MacroKind::Maud);
+    }
+
+    #[test]
+    fn classifies_globs() {
+        let resolver = file_resolver("use maud::*;", &[]);
+        assert_eq!(resolver.classify(&path("html")), MacroKind::Maud);
+
+        let resolver = file_resolver("use yew::prelude::*;", &[]);
+        assert_eq!(resolver.classify(&path("html")), MacroKind::Foreign);
+    }
+
+    #[test]
+    fn classifies_unresolved() {
+        let resolver = file_resolver("", &[]);
+        assert_eq!(resolver.classify(&path("html")), MacroKind::Unresolved);
+        assert_eq!(
+            resolver.classify(&path("crate::html")),
+            MacroKind::Unresolved
+        );
+    }
+
+    #[test]
+    fn leading_colon_bypasses_aliases() {
+        let resolver = file_resolver("use yew as maud;", &[]);
+        assert_eq!(resolver.classify(&path("maud::html")), MacroKind::Foreign);
+        assert_eq!(
+            resolver.classify(&["".into(), "maud".into(), "html".into()]),
+            MacroKind::Maud
+        );
+    }
+
+    #[test]
+    fn classifies_configured_macros() {
+        let resolver = file_resolver("use hypertext::maud;", &["hypertext::maud"]);
+        assert_eq!(resolver.classify(&path("maud")), MacroKind::Maud);
+    }
+
+    #[test]
+    fn conflicting_imports_are_unresolved() {
+        let resolver = file_resolver(
+            r#"
+            #[cfg(feature = "yew")]
+            use yew::html;
+            #[cfg(not(feature = "yew"))]
+            use maud::html;
+            "#,
+            &[],
+        );
+
+        assert_eq!(resolver.classify(&path("html")), MacroKind::Unresolved);
+    }
+
+    #[test]
+    fn modules_do_not_inherit_imports() {
+        let mut resolver = file_resolver("use yew::html;", &[]);
+        assert_eq!(resolver.classify(&path("html")), MacroKind::Foreign);
+
+        resolver.enter_module(&syn::parse_file("use maud::html;").unwrap().items);
+        assert_eq!(resolver.classify(&path("html")), MacroKind::Maud);
+        resolver.exit();
+
+        resolver.enter_module(&[]);
+        assert_eq!(resolver.classify(&path("html")), MacroKind::Unresolved);
+        resolver.exit();
+
+        assert_eq!(resolver.classify(&path("html")), MacroKind::Foreign);
+    }
+
+    #[test]
+    fn blocks_inherit_imports() {
+        let mut resolver = file_resolver("use maud::html;", &[]);
+
+        resolver.enter_block(&[]);
+        assert_eq!(resolver.classify(&path("html")), MacroKind::Maud);
+        resolver.exit();
+
+        let block: syn::Block = syn::parse_str("{ use yew::html; }").unwrap();
+        resolver.enter_block(&block.stmts);
+        assert_eq!(resolver.classify(&path("html")), MacroKind::Foreign);
+        resolver.exit();
+    }
+
+    #[test]
+    fn foreign_module_does_not_hide_other_macros() {
+        let code = r#"
+            use maud::html;
+            fn a() { let v = vec![html! { p { "a" } }]; }
+            mod y {
+                use yew::html;
+                fn b() { html! { <p>{ "b" }</p> } }
+            }
+            mod z {
+                use maud::html;
+                fn c() { html! { p { "c" } } }
+            }
+            fn d() {
+                use yew::html;
+                html! { <div /> };
+            }
+            fn e() { html! { p { "e" } } }
+        "#;
+
+        assert_eq!(formatted(code), [(3, false), (10, true), (16, true)]);
+    }
+
+    #[test]
+    fn macros_inside_token_streams_are_lenient() {
+        let code = r#"
+            use maud::html;
+            macro_rules! page {
+                ($($body:tt)*) => { html! { main.content { $($body)* } } };
+            }
+            fn a() { html! { p { "a" } } }
+        "#;
+
+        assert_eq!(formatted(code), [(4, false), (6, true)]);
+    }
+}
@@ -0,0 +1,137 @@
+use std::ops::Range;
+
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use syn::{spanned::Spanned, visit::Visit, Block, File, ItemMod, Macro};
+
+use crate::resolve::{MacroKind, MacroResolver};
+
+pub struct MacroLocation {
+    pub start_line: usize,
+    pub byte_range: Range<usize>,
+    pub strict: bool,
+}
+
+pub struct MacroVisitor {
+    pub resolver: MacroResolver,
+    pub locations: Vec<MacroLocation>,
+}
+
+fn path_before(tokens: &[TokenTree]) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut rest = tokens;
+
+    while let [init @ .., TokenTree::Ident(ident)] = rest {
+        segments.push(ident.to_string());
+
+        match init {
+            [init @ .., TokenTree::Punct(a), TokenTree::Punct(b)]
+                if a.as_char() == ':' && b.as_char() == ':' =>
+            {
+                if !matches!(init.last(), Some(TokenTree::Ident(_))) {
+                    segments.push(String::new());
+                }
+
+                rest = init
+            }
+            _ => break,
+        }
+    }
+
+    segments.reverse();
+    segments
+}
+
+impl MacroVisitor {
+    fn visit_tokens(&mut self, tokens: TokenStream) {
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+
+        for (i, token) in tokens.iter().enumerate() {
+            let TokenTree::Group(group) = token else {
+                continue;
+            };
+
+            let invocation = match &tokens[..i] {
+                [init @ .., TokenTree::Punct(bang)]
+                    if bang.as_char() == '!' && group.delimiter() == Delimiter::Brace =>
+                {
+                    let path = path_before(init);
+
+                    // Inside another macro's tokens the body may hold metavariables like `$body`,
+                    // so failing to parse it never aborts the file
+                    match self.resolver.classify(&path) {
+                        MacroKind::Maud | MacroKind::Unresolved => {
+                            Some((&init[init.len() - 1], bang))
+                        }
+                        MacroKind::Foreign => continue,
+                        MacroKind::Other => None,
+                    }
+                }
+                _ => None,
+            };
+
+            match invocation {
+                Some((ident, bang)) => self.locations.push(MacroLocation {
+                    start_line: ident.span().start().line,
+                    byte_range: bang.span().byte_range().end..group.span().byte_range().end,
+                    strict: false,
+                }),
+                None => self.visit_tokens(group.stream()),
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for MacroVisitor {
+    fn visit_file(&mut self, file: &'ast File) {
+        self.resolver.enter_module(&file.items);
+        syn::visit::visit_file(self, file);
+        self.resolver.exit();
+    }
+
+    fn visit_item_mod(&mut self, item_mod: &'ast ItemMod) {
+        let Some((_, items)) = &item_mod.content else {
+            return;
+        };
+
+        self.resolver.enter_module(items);
+        syn::visit::visit_item_mod(self, item_mod);
+        self.resolver.exit();
+    }
+
+    fn visit_block(&mut self, block: &'ast Block) {
+        self.resolver.enter_block(&block.stmts);
+        syn::visit::visit_block(self, block);
+        self.resolver.exit();
+    }
+
+    fn visit_macro(&mut self, macro_item: &'ast Macro) {
+        let path: Vec<String> = macro_item
+            .path
+            .leading_colon
+            .map(|_| String::new())
+            .into_iter()
+            .chain(
+                macro_item
+                    .path
+                    .segments
+                    .iter()
+                    .map(|segment| segment.ident.to_string()),
+            )
+            .collect();
+
+        let strict = match self.resolver.classify(&path) {
+            MacroKind::Maud => true,
+            MacroKind::Unresolved => false,
+            MacroKind::Foreign => return,
+            MacroKind::Other => return self.visit_tokens(macro_item.tokens.clone()),
+        };
+
+        let span = macro_item.span();
+
+        self.locations.push(MacroLocation {
+            start_line: span.start().line,
+            byte_range: macro_item.bang_token.span.byte_range().end..span.byte_range().end,
+            strict,
+        });
+    }
+}